@@ -0,0 +1,90 @@
+/// A named mark on a line.
+#[derive(Clone)]
+pub struct Mark {
+    pub name: char,
+    pub no: usize,
+    pub text: String,
+}
+
+/// Named marks on lines, ordered by line.
+///
+/// Marks refer to the absolute line number, so they survive filter
+/// changes and lines arriving later.
+#[derive(Clone, Default)]
+pub struct Bookmarks {
+    marks: Vec<Mark>,
+}
+
+impl Bookmarks {
+    /// Marks line `no`, moving the mark if `name` is already in use.
+    pub fn set(&mut self, name: char, no: usize, text: String) {
+        self.marks.retain(|m| m.name != name);
+        let pos = self.marks.partition_point(|m| m.no <= no);
+        self.marks.insert(pos, Mark { name, no, text });
+    }
+
+    pub fn get(&self, name: char) -> Option<&Mark> {
+        self.marks.iter().find(|m| m.name == name)
+    }
+
+    pub fn remove(&mut self, name: char) {
+        self.marks.retain(|m| m.name != name);
+    }
+
+    /// Name of the first mark on line `no`.
+    pub fn name(&self, no: usize) -> Option<char> {
+        self.marks.iter().find(|m| m.no == no).map(|m| m.name)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Mark> {
+        self.marks.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.marks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(bookmarks: &Bookmarks) -> Vec<(char, usize)> {
+        bookmarks.iter().map(|m| (m.name, m.no)).collect()
+    }
+
+    #[test]
+    fn keeps_marks_ordered_by_line() {
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.set('b', 20, "second".to_owned());
+        bookmarks.set('a', 10, "first".to_owned());
+        bookmarks.set('c', 30, "third".to_owned());
+        assert_eq!(names(&bookmarks), vec![('a', 10), ('b', 20), ('c', 30)]);
+        assert_eq!(bookmarks.name(20), Some('b'));
+        assert_eq!(bookmarks.name(25), None);
+    }
+
+    #[test]
+    fn moves_a_reused_name() {
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.set('a', 10, "first".to_owned());
+        bookmarks.set('b', 20, "second".to_owned());
+        bookmarks.set('a', 30, "third".to_owned());
+        assert_eq!(names(&bookmarks), vec![('b', 20), ('a', 30)]);
+        assert_eq!(bookmarks.get('a').map(|m| m.text.as_str()), Some("third"));
+        assert_eq!(bookmarks.name(10), None);
+    }
+
+    #[test]
+    fn removes_marks() {
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.set('a', 10, "first".to_owned());
+        bookmarks.remove('a');
+        assert!(bookmarks.is_empty());
+        assert!(bookmarks.get('a').is_none());
+    }
+}
@@ -1,11 +1,12 @@
 use arraydeque::behavior::Wrapping;
 use arraydeque::ArrayDeque;
 
-use crate::source::{Source, TryRead};
+use crate::source::{Source, SourceState, TryRead};
 
 pub struct SourceBuffer<A: Send> {
     buffer: ArrayDeque<A, 1024, Wrapping>,
     handle: Source<A>,
+    count: usize,
 }
 
 impl<'a, A: 'a + Send + Clone> SourceBuffer<A> {
@@ -13,19 +14,45 @@ impl<'a, A: 'a + Send + Clone> SourceBuffer<A> {
         Self {
             buffer: ArrayDeque::new(),
             handle,
+            count: 0,
         }
     }
 
     pub fn update(&'a mut self) -> Option<A> {
         if let Some(c) = self.handle.try_read() {
             self.buffer.push_back(c.clone());
+            self.count += 1;
             Some(c)
         } else {
             None
         }
     }
 
-    pub fn iter(&'a self) -> arraydeque::Iter<A> {
+    pub fn iter(&'a self) -> arraydeque::Iter<'a, A> {
         self.buffer.iter()
     }
+
+    /// Number of lines received so far, which is also the line number
+    /// the next line will get.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Line number of the oldest line still in the buffer.
+    pub fn first(&self) -> usize {
+        self.count - self.buffer.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn state(&self) -> SourceState {
+        self.handle.state()
+    }
+
+    pub fn get(&'a self, no: usize) -> Option<&'a A> {
+        no.checked_sub(self.first())
+            .and_then(|i| self.buffer.get(i))
+    }
 }
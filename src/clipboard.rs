@@ -0,0 +1,46 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::Error;
+
+pub struct Clipboard {}
+
+impl Clipboard {
+    /// Copies text with an OSC 52 sequence, which also works over SSH.
+    /// Inside tmux the sequence is passed through to the outer terminal.
+    pub fn copy(text: &str) -> Result<(), Error> {
+        let mut sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+        if std::env::var_os("TMUX").is_some() {
+            sequence = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+        }
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/tty")
+            .and_then(|mut tty| tty.write_all(sequence.as_bytes()))
+            .map_err(|_| Error::CopyFailed)
+    }
+
+    /// Pipes text to a command such as `xclip -selection clipboard` or
+    /// `wl-copy`, run by the shell.
+    pub fn pipe(command: &str, text: &str) -> Result<(), Error> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|_| Error::CopyFailed)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|_| Error::CopyFailed)?;
+        }
+        match child.wait() {
+            Ok(status) if status.success() => Ok(()),
+            _ => Err(Error::CopyFailed),
+        }
+    }
+}
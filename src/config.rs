@@ -0,0 +1,87 @@
+use cursive::theme::{Color, ColorStyle, ColorType, Effect, Style};
+use regex::Regex;
+use std::path::PathBuf;
+
+use crate::error::Error;
+
+/// Settings read from the configuration file.
+#[derive(Default)]
+pub struct Config {
+    pub formats: Vec<(String, String)>,
+    pub highlights: Vec<(Regex, Style)>,
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let value: toml::Value = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| Error::ConfigFailed(path.to_owned()))?;
+
+        let formats = value
+            .get("formats")
+            .and_then(toml::Value::as_table)
+            .map(|t| {
+                t.iter()
+                    .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_owned())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let highlights = match value.get("highlight").and_then(toml::Value::as_array) {
+            Some(rules) => rules
+                .iter()
+                .map(Config::highlight)
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            formats,
+            highlights,
+        })
+    }
+
+    /// Parses a `[[highlight]]` rule with a `regex` and optional `fg`,
+    /// `bg` and `effects`.
+    fn highlight(rule: &toml::Value) -> Result<(Regex, Style), Error> {
+        let field = |key: &str| rule.get(key).and_then(toml::Value::as_str);
+        let pattern = field("regex").unwrap_or_default();
+        let invalid = || Error::InvalidHighlight(pattern.to_owned());
+        if pattern.is_empty() {
+            return Err(invalid());
+        }
+        let regex = Regex::new(pattern).map_err(|_| invalid())?;
+        let color = |key: &str| match field(key) {
+            Some(c) => Color::parse(c).map(ColorType::Color).ok_or_else(invalid),
+            None => Ok(ColorType::InheritParent),
+        };
+        let mut style = Style::from(ColorStyle::new(color("fg")?, color("bg")?));
+        let effects = rule.get("effects").and_then(toml::Value::as_array);
+        for effect in effects.into_iter().flatten() {
+            style.effects |= match effect.as_str() {
+                Some("bold") => Effect::Bold,
+                Some("italic") => Effect::Italic,
+                Some("underline") => Effect::Underline,
+                Some("reverse") => Effect::Reverse,
+                Some("dim") => Effect::Dim,
+                Some("blink") => Effect::Blink,
+                Some("strikethrough") => Effect::Strikethrough,
+                _ => return Err(invalid()),
+            };
+        }
+        Ok((regex, style))
+    }
+
+    /// Returns `$XDG_CONFIG_HOME/logview/config.toml` if it exists, with
+    /// `$XDG_CONFIG_HOME` defaulting to `~/.config`.
+    pub fn default_path() -> Option<String> {
+        std::env::var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|_| std::env::var("HOME").map(|h| PathBuf::from(h).join(".config")))
+            .ok()
+            .map(|p| p.join("logview").join("config.toml"))
+            .filter(|p| p.exists())
+            .map(|p| p.to_string_lossy().into_owned())
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+
+/// A line of the content view produced by [`Context::process`].
+pub enum Line<T> {
+    Match(T),
+    Context(T),
+    Separator,
+}
+
+/// Tracks the grep-style context around filter matches.
+///
+/// Lines are identified by `T`, usually carrying the absolute line
+/// number, so the context can be computed for lines replayed from the
+/// buffer as well as for newly arriving lines. Lines never passed to
+/// [`Context::process`] (e.g. muted ones) don't count as context.
+pub struct Context<T> {
+    before: usize,
+    after: usize,
+    pending: usize,
+    skipped: usize,
+    recent: VecDeque<T>,
+    shown: bool,
+}
+
+impl<T> Context<T> {
+    pub fn new(before: usize, after: usize) -> Self {
+        Self {
+            before,
+            after,
+            pending: 0,
+            skipped: 0,
+            recent: VecDeque::with_capacity(before),
+            shown: false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.pending = 0;
+        self.skipped = 0;
+        self.recent.clear();
+        self.shown = false;
+    }
+
+    /// Returns the lines to show once line `no` is received.
+    pub fn process(&mut self, no: T, is_match: bool) -> Vec<Line<T>> {
+        let mut lines = Vec::new();
+        if is_match {
+            if self.shown && self.skipped > self.recent.len() && (self.before > 0 || self.after > 0)
+            {
+                lines.push(Line::Separator);
+            }
+            lines.extend(self.recent.drain(..).map(Line::Context));
+            lines.push(Line::Match(no));
+            self.skipped = 0;
+            self.pending = self.after;
+            self.shown = true;
+        } else if self.pending > 0 {
+            lines.push(Line::Context(no));
+            self.pending -= 1;
+        } else {
+            self.skipped += 1;
+            if self.before > 0 {
+                if self.recent.len() == self.before {
+                    self.recent.pop_front();
+                }
+                self.recent.push_back(no);
+            }
+        }
+        lines
+    }
+}
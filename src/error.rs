@@ -2,6 +2,18 @@ pub enum Error {
     NoPipeIn,
     FileOpenFailed(String),
     CustomThemeFailed(String),
+    ConfigFailed(String),
+    InvalidFormat(String),
+    UnknownFormat(String),
+    InvalidPattern(regex::Error),
+    InvalidTime(String),
+    UnknownCommand(String),
+    CopyFailed,
+    InvalidHighlight(String),
+    WriteFailed(String),
+    NoConfig,
+    Usage(String),
+    FileExists(String, String),
 }
 
 impl std::fmt::Debug for Error {
@@ -17,6 +29,28 @@ impl std::fmt::Debug for Error {
             Error::CustomThemeFailed(s) => {
                 f.write_fmt(format_args!("Couldn't load custom theme from '{}'.", s))
             }
+            Error::ConfigFailed(s) => {
+                f.write_fmt(format_args!("Couldn't load configuration from '{}'.", s))
+            }
+            Error::InvalidFormat(s) => f.write_fmt(format_args!(
+                "The format '{}' is not a valid regular expression.",
+                s
+            )),
+            Error::UnknownFormat(s) => f.write_fmt(format_args!("Unknown format '{}'.", s)),
+            Error::InvalidPattern(e) => f.write_fmt(format_args!("{:?}", e)),
+            Error::InvalidTime(s) => f.write_fmt(format_args!("Couldn't parse the time '{}'.", s)),
+            Error::UnknownCommand(s) => f.write_fmt(format_args!("Unknown command ':{}'.", s)),
+            Error::CopyFailed => f.write_fmt(format_args!("Couldn't copy to the clipboard.")),
+            Error::InvalidHighlight(s) => {
+                f.write_fmt(format_args!("The highlight rule '{}' is not valid.", s))
+            }
+            Error::WriteFailed(s) => f.write_fmt(format_args!("Couldn't write to '{}'.", s)),
+            Error::NoConfig => f.write_fmt(format_args!("No config file to reload.")),
+            Error::Usage(s) => f.write_fmt(format_args!("Usage: {}", s)),
+            Error::FileExists(s, c) => f.write_fmt(format_args!(
+                "'{}' already exists, use :{}! to overwrite it.",
+                s, c
+            )),
         }
     }
 }
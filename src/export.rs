@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use cursive::theme::{Color, ColorType, Effect, Palette, PaletteColor, Style};
+use cursive::utils::span::SpannedString;
+
+use crate::error::Error;
+use crate::format::Formats;
+use crate::level::Level;
+use crate::string::ColoredString;
+
+const DARK: [&str; 8] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+];
+const LIGHT: [&str; 8] = [
+    "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Plain,
+    Ansi,
+    Html,
+    Json,
+}
+
+impl Format {
+    /// Picks the format from the file extension, plain text by default.
+    pub fn from_path(path: &str) -> Format {
+        match path
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+        {
+            Some(ext) if ext == "ansi" => Format::Ansi,
+            Some(ext) if ext == "html" || ext == "htm" => Format::Html,
+            Some(ext) if ext == "json" || ext == "jsonl" || ext == "ndjson" => Format::Json,
+            _ => Format::Plain,
+        }
+    }
+}
+
+/// A line to export with its label as shown in the view, its level and
+/// time. Separators have no line number.
+pub struct Entry {
+    pub no: Option<usize>,
+    pub text: String,
+    pub label: SpannedString<Style>,
+    pub level: Option<Level>,
+    pub time: Option<DateTime<Utc>>,
+}
+
+/// Writes lines to a file as plain text, text with the input's escape
+/// sequences, standalone HTML in the colors of the view or JSON Lines
+/// with the parsed fields.
+pub fn write(
+    path: &str,
+    format: Format,
+    entries: &[Entry],
+    formats: &Formats,
+    palette: &Palette,
+) -> Result<(), Error> {
+    let output = match format {
+        Format::Plain => lines(entries, |e| ColoredString::strip(&e.text)),
+        Format::Ansi => lines(entries, |e| e.text.clone()),
+        Format::Html => html(entries, palette),
+        Format::Json => json(entries, formats),
+    };
+    std::fs::write(path, output).map_err(|_| Error::WriteFailed(path.to_owned()))
+}
+
+fn lines(entries: &[Entry], text: impl Fn(&Entry) -> String) -> String {
+    entries
+        .iter()
+        .map(|e| match e.no {
+            Some(_) => format!("{}\n", text(e).trim_end_matches(['\r', '\n'])),
+            None => "--\n".to_owned(),
+        })
+        .collect()
+}
+
+fn html(entries: &[Entry], palette: &Palette) -> String {
+    let mut body = String::new();
+    for entry in entries.iter() {
+        if entry.no.is_none() {
+            body.push_str("--\n");
+            continue;
+        }
+        for span in entry.label.spans() {
+            let content = escape(span.content.trim_end_matches(['\r', '\n']));
+            match css(span.attr, palette) {
+                Some(style) => {
+                    body.push_str(&format!("<span style=\"{}\">{}</span>", style, content))
+                }
+                None => body.push_str(&content),
+            }
+        }
+        body.push('\n');
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>logview</title>\n\
+         <style>body {{ background: {}; color: {}; }}</style>\n</head>\n<body>\n<pre>\n{}</pre>\n\
+         </body>\n</html>\n",
+        color(&palette[PaletteColor::View]).unwrap_or_else(|| "#000000".to_owned()),
+        color(&palette[PaletteColor::Primary]).unwrap_or_else(|| "#ffffff".to_owned()),
+        body
+    )
+}
+
+fn json(entries: &[Entry], formats: &Formats) -> String {
+    let mut output = String::new();
+    for entry in entries.iter() {
+        let no = match entry.no {
+            Some(no) => no,
+            None => continue,
+        };
+        let text = ColoredString::strip(&entry.text);
+        let text = text.trim_end_matches(['\r', '\n']);
+        let fields: serde_json::Map<String, serde_json::Value> = formats
+            .parse(text)
+            .map(|f| {
+                f.iter()
+                    .map(|(k, v)| (k.clone(), serde_json::Value::from(v.as_str())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let line = serde_json::json!({
+            "line": no + 1,
+            "level": entry.level.map(|l| l.name()),
+            "time": entry.time.map(|t| t.to_rfc3339()),
+            "text": text,
+            "fields": fields,
+        });
+        output.push_str(&format!("{}\n", line));
+    }
+    output
+}
+
+fn css(style: &Style, palette: &Palette) -> Option<String> {
+    let resolve = |c: &ColorType| match c {
+        ColorType::Palette(p) => color(&palette[*p]),
+        ColorType::Color(c) => color(c),
+        ColorType::InheritParent => None,
+    };
+    let mut css = Vec::new();
+    if let Some(c) = resolve(&style.color.front) {
+        css.push(format!("color: {}", c));
+    }
+    if let Some(c) = resolve(&style.color.back) {
+        css.push(format!("background: {}", c));
+    }
+    if style.effects.contains(Effect::Bold) {
+        css.push("font-weight: bold".to_owned());
+    }
+    if style.effects.contains(Effect::Italic) {
+        css.push("font-style: italic".to_owned());
+    }
+    if style.effects.contains(Effect::Underline) {
+        css.push("text-decoration: underline".to_owned());
+    }
+    if style.effects.contains(Effect::Dim) {
+        css.push("opacity: 0.6".to_owned());
+    }
+    match css.is_empty() {
+        true => None,
+        false => Some(css.join("; ")),
+    }
+}
+
+fn color(color: &Color) -> Option<String> {
+    match color {
+        Color::TerminalDefault => None,
+        Color::Dark(c) => Some(DARK[*c as usize].to_owned()),
+        Color::Light(c) => Some(LIGHT[*c as usize].to_owned()),
+        Color::Rgb(r, g, b) => Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        Color::RgbLowRes(r, g, b) => Some(format!("#{:02x}{:02x}{:02x}", r * 51, g * 51, b * 51)),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(no: Option<usize>, text: &str) -> Entry {
+        Entry {
+            no,
+            text: text.to_owned(),
+            label: SpannedString::<Style>::plain(text),
+            level: None,
+            time: None,
+        }
+    }
+
+    #[test]
+    fn picks_the_format_from_the_extension() {
+        assert!(matches!(Format::from_path("view.ansi"), Format::Ansi));
+        assert!(matches!(Format::from_path("view.HTML"), Format::Html));
+        assert!(matches!(Format::from_path("view.htm"), Format::Html));
+        assert!(matches!(Format::from_path("view.jsonl"), Format::Json));
+        assert!(matches!(Format::from_path("view.ndjson"), Format::Json));
+        assert!(matches!(Format::from_path("view.txt"), Format::Plain));
+        assert!(matches!(Format::from_path("view"), Format::Plain));
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(escape("a < b && c > d"), "a &lt; b &amp;&amp; c &gt; d");
+        assert_eq!(escape("&lt;"), "&amp;lt;");
+    }
+
+    #[test]
+    fn writes_one_object_per_line() {
+        let entries = [
+            entry(Some(0), "{\"level\":\"warn\",\"msg\":\"disk\"}\n"),
+            entry(None, ""),
+            entry(Some(4), "\x1b[31mplain\x1b[0m"),
+        ];
+        let output = json(&entries, &Formats::default());
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["line"], 1);
+        assert_eq!(lines[0]["text"], "{\"level\":\"warn\",\"msg\":\"disk\"}");
+        assert_eq!(lines[0]["fields"]["msg"], "disk");
+        assert_eq!(lines[0]["level"], serde_json::Value::Null);
+        assert_eq!(lines[1]["line"], 5);
+        assert_eq!(lines[1]["text"], "plain");
+        assert_eq!(lines[1]["fields"], serde_json::json!({}));
+    }
+}
@@ -0,0 +1,238 @@
+use cursive::theme::{Effect, Style};
+use cursive::utils::span::SpannedString;
+use regex::Regex;
+use std::sync::OnceLock;
+
+pub const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Named values parsed from a structured line, in input order.
+#[derive(Default)]
+pub struct Fields {
+    values: Vec<(String, String)>,
+}
+
+impl Fields {
+    pub fn push(&mut self, key: String, value: String) {
+        self.values.push((key, value));
+    }
+
+    /// Sets the value of `key`, replacing an existing value in place.
+    pub fn insert(&mut self, key: &str, value: String) {
+        match self.values.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.values.push((key.to_owned(), value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let i = self.values.iter().position(|(k, _)| k == key)?;
+        Some(self.values.remove(i).1)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(String, String)> {
+        self.values.iter()
+    }
+}
+
+/// Renders fields as aligned columns followed by the remaining fields.
+pub struct Columns {
+    names: Vec<String>,
+    widths: Vec<usize>,
+    key: Style,
+    value: Style,
+}
+
+impl Columns {
+    pub fn new(names: Vec<String>, key: Style, value: Style) -> Self {
+        let widths = vec![0; names.len()];
+        Self {
+            names,
+            widths,
+            key,
+            value,
+        }
+    }
+
+    /// Forgets the column widths before the view is replayed.
+    pub fn reset(&mut self) {
+        self.widths.fill(0);
+    }
+
+    /// Widens the columns to fit the values of `fields`.
+    pub fn measure(&mut self, fields: &Fields) {
+        for (i, name) in self.names.iter().enumerate() {
+            let width = fields.get(name).map_or(0, |v| v.chars().count());
+            self.widths[i] = self.widths[i].max(width.min(MAX_COLUMN_WIDTH));
+        }
+    }
+
+    pub fn render(&mut self, fields: &Fields) -> SpannedString<Style> {
+        self.measure(fields);
+        let mut output = SpannedString::new();
+        for (i, name) in self.names.iter().enumerate() {
+            let value = fields.get(name).unwrap_or("");
+            if i + 1 < self.names.len() {
+                let value = Columns::truncate(value, self.widths[i]);
+                let width = value.chars().count();
+                output.append(format!(
+                    "{}{} ",
+                    value,
+                    " ".repeat(self.widths[i].saturating_sub(width))
+                ));
+            } else {
+                output.append(value);
+            }
+        }
+        for (k, v) in fields.iter().filter(|(k, _)| !self.names.contains(k)) {
+            let v = if v.is_empty() || v.contains(|c: char| c.is_whitespace() || c == '"') {
+                format!("{:?}", v)
+            } else {
+                v.clone()
+            };
+            output.append(SpannedString::styled(
+                format!(" {}", k),
+                self.key.combine(Effect::Dim),
+            ));
+            output.append(SpannedString::styled("=", Effect::Dim));
+            output.append(SpannedString::styled(v, self.value.combine(Effect::Dim)));
+        }
+        output
+    }
+
+    fn truncate(value: &str, width: usize) -> String {
+        match value.chars().count() > width {
+            true => value
+                .chars()
+                .take(width.saturating_sub(1))
+                .chain(Some('…'))
+                .collect(),
+            false => value.to_owned(),
+        }
+    }
+}
+
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match(Regex),
+}
+
+/// Filter on a single field, written as `.field op value`.
+pub struct FieldFilter {
+    field: String,
+    op: Op,
+    value: String,
+}
+
+impl FieldFilter {
+    /// Returns `None` if `s` isn't a field expression at all.
+    pub fn parse(s: &str) -> Option<Result<FieldFilter, regex::Error>> {
+        static EXPR: OnceLock<Regex> = OnceLock::new();
+        let expr = EXPR
+            .get_or_init(|| Regex::new(r"^\.([\w.\-]+)\s*(==|!=|=~|<=|>=|<|>)\s*(.*)$").unwrap());
+        let c = expr.captures(s.trim())?;
+        let mut value = c[3].trim();
+        if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            value = &value[1..value.len() - 1];
+        }
+        let op = match &c[2] {
+            "==" => Op::Eq,
+            "!=" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            _ => match Regex::new(value) {
+                Ok(r) => Op::Match(r),
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        Some(Ok(FieldFilter {
+            field: c[1].to_owned(),
+            op,
+            value: value.to_owned(),
+        }))
+    }
+
+    pub fn matches(&self, fields: &Fields) -> bool {
+        let actual = match fields.get(&self.field) {
+            Some(v) => v,
+            None => return false,
+        };
+        let ordering = match (actual.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b),
+            (_, _) => Some(actual.cmp(self.value.as_str())),
+        };
+        match &self.op {
+            Op::Eq => ordering == Some(std::cmp::Ordering::Equal),
+            Op::Ne => ordering != Some(std::cmp::Ordering::Equal),
+            Op::Lt => ordering == Some(std::cmp::Ordering::Less),
+            Op::Le => ordering.is_some_and(|o| o != std::cmp::Ordering::Greater),
+            Op::Gt => ordering == Some(std::cmp::Ordering::Greater),
+            Op::Ge => ordering.is_some_and(|o| o != std::cmp::Ordering::Less),
+            Op::Match(r) => r.is_match(actual),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> Fields {
+        let mut fields = Fields::default();
+        for (k, v) in pairs.iter() {
+            fields.push(k.to_string(), v.to_string());
+        }
+        fields
+    }
+
+    fn columns() -> Columns {
+        let names = vec!["level".to_owned(), "msg".to_owned()];
+        Columns::new(names, Style::none(), Style::none())
+    }
+
+    #[test]
+    fn aligns_columns_and_appends_other_fields() {
+        let mut columns = columns();
+        columns.measure(&fields(&[("level", "error")]));
+        let line = columns.render(&fields(&[("level", "info"), ("msg", "up"), ("a", "x y")]));
+        assert_eq!(line.source(), "info  up a=\"x y\"");
+    }
+
+    #[test]
+    fn truncates_long_values() {
+        let mut columns = columns();
+        let long = "x".repeat(MAX_COLUMN_WIDTH + 10);
+        let line = columns.render(&fields(&[("level", &long), ("msg", "m")]));
+        let expected = format!("{}… m", "x".repeat(MAX_COLUMN_WIDTH - 1));
+        assert_eq!(line.source(), expected);
+        columns.reset();
+        let line = columns.render(&fields(&[("level", "a"), ("msg", "m")]));
+        assert_eq!(line.source(), "a m");
+    }
+
+    #[test]
+    fn filters_on_field_values() {
+        let line = fields(&[("status", "404"), ("path", "/api/users")]);
+        let matches = |s: &str| FieldFilter::parse(s).unwrap().unwrap().matches(&line);
+        assert!(matches(".status >= 400"));
+        assert!(matches(".status == \"404\""));
+        assert!(!matches(".status < 100"));
+        assert!(matches(".path =~ ^/api"));
+        assert!(!matches(".missing == 1"));
+        assert!(FieldFilter::parse("status == 404").is_none());
+        assert!(FieldFilter::parse(".path =~ (").unwrap().is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::error::Error;
+use crate::fields::Fields;
+use crate::level::Level;
+use crate::parser::{JsonParser, LogfmtParser};
+use crate::string::ColoredString;
+
+#[derive(Clone)]
+enum Kind {
+    Regex(Regex),
+    Docker,
+    Cri,
+    Json,
+    Logfmt,
+    Access,
+    Syslog,
+    Journal,
+}
+
+#[derive(Clone)]
+struct Format {
+    name: String,
+    kind: Kind,
+}
+
+impl Format {
+    fn builtin(name: &str, kind: Kind) -> Self {
+        Self {
+            name: name.to_owned(),
+            kind,
+        }
+    }
+
+    /// Parses a line without escape sequences. Container lines are handled
+    /// by `unwrap` instead.
+    fn parse(&self, line: &str) -> Option<Fields> {
+        static ACCESS: OnceLock<Regex> = OnceLock::new();
+        static SYSLOG: OnceLock<Regex> = OnceLock::new();
+        static JOURNAL: OnceLock<Regex> = OnceLock::new();
+
+        match &self.kind {
+            Kind::Regex(r) => Format::captures(r, line),
+            Kind::Json => JsonParser::parse(line),
+            Kind::Logfmt => LogfmtParser::parse(line),
+            Kind::Access => {
+                let access = ACCESS.get_or_init(|| {
+                    Regex::new(
+                        r#"^(?P<source>\S+) \S+ (?P<user>\S+) \[(?P<ts>[^\]]+)\] "(?P<msg>[^"]*)" (?P<status>\d{3}) (?P<bytes>\d+|-)(?: "(?P<referer>[^"]*)" "(?P<agent>[^"]*)")?"#,
+                    )
+                    .unwrap()
+                });
+                Format::captures(access, line).map(|mut f| {
+                    let level = match f.get("status").map(|s| &s[..1]) {
+                        Some("5") => Level::Error,
+                        Some("4") => Level::Warn,
+                        _ => Level::Info,
+                    };
+                    f.insert("level", level.name().to_owned());
+                    f
+                })
+            }
+            Kind::Syslog | Kind::Journal => {
+                let regex = if let Kind::Syslog = self.kind {
+                    SYSLOG.get_or_init(|| {
+                        Regex::new(
+                            r"^(?:<(?P<pri>\d{1,3})>)?(?P<ts>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?P<host>\S+) (?P<source>[^\s:\[]+)(?:\[(?P<pid>\d+)\])?: (?P<msg>.*)$",
+                        )
+                        .unwrap()
+                    })
+                } else {
+                    JOURNAL.get_or_init(|| {
+                        Regex::new(
+                            r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:?\d{2})) (?P<host>\S+) (?P<source>[^\s:\[]+)(?:\[(?P<pid>\d+)\])?: (?P<msg>.*)$",
+                        )
+                        .unwrap()
+                    })
+                };
+                Format::captures(regex, line).map(|mut f| {
+                    if let Some(p) = f.remove("pri").and_then(|p| p.parse::<u8>().ok()) {
+                        f.insert("level", Level::from_severity(p % 8).name().to_owned());
+                    }
+                    f
+                })
+            }
+            Kind::Docker | Kind::Cri => None,
+        }
+    }
+
+    /// Splits a container line into its fields and the wrapped message,
+    /// which keeps its escape sequences.
+    fn unwrap(&self, line: &str) -> Option<(Fields, String)> {
+        static CRI: OnceLock<Regex> = OnceLock::new();
+
+        match &self.kind {
+            Kind::Cri => {
+                let cri = CRI.get_or_init(|| {
+                    Regex::new(
+                        r"^(?P<ts>\d{4}-\d{2}-\d{2}T\S+) (?P<source>stdout|stderr) (?P<tag>[FP]) ?(?P<msg>.*)$",
+                    )
+                    .unwrap()
+                });
+                let mut fields = Format::captures(cri, line)?;
+                fields.remove("tag");
+                let msg = fields.remove("msg").unwrap_or_default();
+                Some((fields, msg))
+            }
+            Kind::Docker => {
+                let json = JsonParser::parse(line)?;
+                let msg = json.get("log")?.trim_end_matches(['\r', '\n']).to_owned();
+                let mut fields = Fields::default();
+                fields.push("ts".to_owned(), json.get("time")?.to_owned());
+                if let Some(s) = json.get("stream") {
+                    fields.push("source".to_owned(), s.to_owned());
+                }
+                Some((fields, msg))
+            }
+            _ => None,
+        }
+    }
+
+    fn is_container(&self) -> bool {
+        matches!(self.kind, Kind::Docker | Kind::Cri)
+    }
+
+    fn captures(regex: &Regex, line: &str) -> Option<Fields> {
+        regex.captures(line).map(|c| {
+            let mut fields = Fields::default();
+            for name in regex.capture_names().flatten() {
+                if let Some(m) = c.name(name) {
+                    fields.push(name.to_owned(), m.as_str().to_owned());
+                }
+            }
+            fields
+        })
+    }
+}
+
+/// The structured line formats, tried in order on every line. Messages
+/// of container lines are tried with the formats that aren't containers.
+#[derive(Clone)]
+pub struct Formats {
+    formats: Vec<Format>,
+    inner: Vec<Format>,
+}
+
+impl Default for Formats {
+    fn default() -> Self {
+        Self::from(Self::builtin())
+    }
+}
+
+impl Formats {
+    /// Creates the formats from user-defined `(name, regex)` pairs. These
+    /// are tried before the built-in formats.
+    pub fn new(user: &[(String, String)]) -> Result<Self, Error> {
+        let mut formats = Vec::new();
+        for (name, expr) in user.iter() {
+            let regex = Regex::new(expr).map_err(|_| Error::InvalidFormat(name.clone()))?;
+            formats.push(Format {
+                name: name.clone(),
+                kind: Kind::Regex(regex),
+            });
+        }
+        formats.extend(Self::builtin());
+        Ok(Self::from(formats))
+    }
+
+    fn builtin() -> Vec<Format> {
+        vec![
+            Format::builtin("docker", Kind::Docker),
+            Format::builtin("cri", Kind::Cri),
+            Format::builtin("json", Kind::Json),
+            Format::builtin("logfmt", Kind::Logfmt),
+            Format::builtin("access", Kind::Access),
+            Format::builtin("syslog", Kind::Syslog),
+            Format::builtin("journal", Kind::Journal),
+        ]
+    }
+
+    fn from(formats: Vec<Format>) -> Self {
+        let inner = formats
+            .iter()
+            .filter(|f| !f.is_container())
+            .cloned()
+            .collect();
+        Self { formats, inner }
+    }
+
+    /// Restricts parsing to the format with the given name. Messages of
+    /// a selected container format are still parsed with all the others.
+    pub fn select(&mut self, name: &str) -> Result<(), Error> {
+        self.formats.retain(|f| f.name == name);
+        if self.formats.is_empty() {
+            Err(Error::UnknownFormat(name.to_owned()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Parses a line without escape sequences, returning `None` if it
+    /// isn't in any of the formats. The fields of a structured message in
+    /// a container line are merged into the container fields.
+    pub fn parse(&self, line: &str) -> Option<Fields> {
+        self.parse_message(line).map(|(fields, _)| fields)
+    }
+
+    /// Like [`Formats::parse`] for a line that may contain escape
+    /// sequences. Also returns the unstructured message of a container
+    /// line, keeping its escape sequences.
+    pub fn parse_message(&self, line: &str) -> Option<(Fields, Option<String>)> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let plain = ColoredString::strip(line);
+        self.formats.iter().find_map(|f| match f.unwrap(&plain) {
+            Some((mut fields, msg)) => {
+                let stripped = ColoredString::strip(&msg);
+                match self.inner.iter().find_map(|f| f.parse(&stripped)) {
+                    Some(inner) => {
+                        for (k, v) in inner.iter() {
+                            fields.insert(k, v.clone());
+                        }
+                        Some((fields, None))
+                    }
+                    None => {
+                        let msg = match plain == line {
+                            true => msg,
+                            false => f.unwrap(line).map_or(msg, |(_, m)| m),
+                        };
+                        fields.push("msg".to_owned(), stripped);
+                        Some((fields, Some(msg)))
+                    }
+                }
+            }
+            None => f.parse(&plain).map(|fields| (fields, None)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCKER: &str = r#"{"log":"{\"level\":\"warn\",\"msg\":\"disk full\"}\n","stream":"stderr","time":"2024-05-01T10:00:00.5Z"}"#;
+
+    #[test]
+    fn unwraps_docker_lines() {
+        let (fields, msg) = Formats::default().parse_message(DOCKER).unwrap();
+        assert_eq!(fields.get("ts"), Some("2024-05-01T10:00:00.5Z"));
+        assert_eq!(fields.get("source"), Some("stderr"));
+        assert_eq!(fields.get("level"), Some("warn"));
+        assert_eq!(fields.get("msg"), Some("disk full"));
+        assert!(msg.is_none());
+    }
+
+    #[test]
+    fn unwraps_cri_lines() {
+        let line = "2024-05-01T10:00:00.5Z stdout F level=info msg=started port=80";
+        let fields = Formats::default().parse(line).unwrap();
+        assert_eq!(fields.get("source"), Some("stdout"));
+        assert_eq!(fields.get("tag"), None);
+        assert_eq!(fields.get("msg"), Some("started"));
+        assert_eq!(fields.get("port"), Some("80"));
+    }
+
+    #[test]
+    fn keeps_escape_sequences_of_plain_messages() {
+        let line = "2024-05-01T10:00:00Z stderr F \x1b[31mfailed\x1b[0m";
+        let (fields, msg) = Formats::default().parse_message(line).unwrap();
+        assert_eq!(fields.get("msg"), Some("failed"));
+        assert_eq!(msg.as_deref(), Some("\x1b[31mfailed\x1b[0m"));
+    }
+
+    #[test]
+    fn parses_messages_of_a_selected_container_format() {
+        let mut formats = Formats::default();
+        formats.select("docker").unwrap();
+        let fields = formats.parse(DOCKER).unwrap();
+        assert_eq!(fields.get("level"), Some("warn"));
+        assert!(formats.parse(r#"{"level":"warn"}"#).is_none());
+        assert!(formats.select("xml").is_err());
+    }
+}
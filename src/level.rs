@@ -0,0 +1,96 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    pub const ALL: [Level; 6] = [
+        Level::Trace,
+        Level::Debug,
+        Level::Info,
+        Level::Warn,
+        Level::Error,
+        Level::Fatal,
+    ];
+
+    /// Theme color key of the level.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Level::Trace => "level_trace",
+            Level::Debug => "level_debug",
+            Level::Info => "level_info",
+            Level::Warn => "level_warn",
+            Level::Error => "level_error",
+            Level::Fatal => "level_fatal",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+            Level::Fatal => "fatal",
+        }
+    }
+
+    /// Detects the level of a line without escape sequences.
+    ///
+    /// Recognizes syslog priorities (`<3>`), glog prefixes
+    /// (`E0102 10:42:00.123456`) and the common level tokens.
+    pub fn detect(line: &str) -> Option<Level> {
+        static SYSLOG: OnceLock<Regex> = OnceLock::new();
+        static GLOG: OnceLock<Regex> = OnceLock::new();
+        static TOKEN: OnceLock<Regex> = OnceLock::new();
+
+        let syslog = SYSLOG.get_or_init(|| Regex::new(r"^<(\d{1,3})>").unwrap());
+        if let Some(c) = syslog.captures(line) {
+            return c[1].parse::<u8>().ok().map(|p| Level::from_severity(p % 8));
+        }
+        let glog = GLOG.get_or_init(|| Regex::new(r"^([IWEF])\d{4} \d{2}:\d{2}:\d{2}").unwrap());
+        if let Some(c) = glog.captures(line) {
+            return Level::from_token(&c[1]);
+        }
+        let token = TOKEN.get_or_init(|| {
+            Regex::new(
+                r#"(?i)(?:^|[\s\[<(|:=,"])(trace|debug|info|notice|warn(?:ing)?|err(?:or)?|crit(?:ical)?|alert|emerg|fatal|panic)(?:$|[\s\]>)|:,"])"#,
+            )
+            .unwrap()
+        });
+        token.captures(line).and_then(|c| Level::from_token(&c[1]))
+    }
+
+    /// Maps a syslog severity (0 = emerg, 7 = debug) to a level.
+    pub fn from_severity(severity: u8) -> Level {
+        match severity {
+            0..=2 => Level::Fatal,
+            3 => Level::Error,
+            4 => Level::Warn,
+            5 | 6 => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+
+    /// Maps a level name such as `WARNING` or `err` to a level.
+    pub fn from_token(token: &str) -> Option<Level> {
+        match token.to_ascii_lowercase().as_str() {
+            "trace" => Some(Level::Trace),
+            "debug" => Some(Level::Debug),
+            "i" | "info" | "notice" => Some(Level::Info),
+            "w" | "warn" | "warning" => Some(Level::Warn),
+            "e" | "err" | "error" => Some(Level::Error),
+            "f" | "crit" | "critical" | "alert" | "emerg" | "fatal" | "panic" => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+}
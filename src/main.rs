@@ -1,11 +1,28 @@
+mod bookmark;
 mod buffer;
+mod clipboard;
+mod config;
+mod context;
 mod error;
+mod export;
+mod fields;
+mod format;
+mod level;
+mod note;
 mod parser;
+mod pattern;
+mod semantic;
 mod source;
+mod status;
 mod string;
+mod table;
+mod timestamp;
+mod tree;
 mod tui;
 
+use crate::config::Config;
 use crate::error::Error;
+use crate::format::Formats;
 use crate::source::{AsyncFileIn, AsyncPipeIn, Source};
 use crate::tui::{Mode, Tui};
 
@@ -34,6 +51,45 @@ struct Args {
     /// Store history on quit
     #[arg(long)]
     history: Option<String>,
+
+    /// Lines of context after each filter match
+    #[arg(short = 'A', long)]
+    after_context: Option<usize>,
+
+    /// Lines of context before each filter match
+    #[arg(short = 'B', long)]
+    before_context: Option<usize>,
+
+    /// Lines of context before and after each filter match, unless given
+    /// by -A or -B
+    #[arg(short = 'C', long)]
+    context: Option<usize>,
+
+    /// Filter while typing
+    #[arg(short, long)]
+    incremental: bool,
+
+    /// Fields shown as columns for structured lines
+    #[arg(long, value_delimiter = ',', default_value = "ts,level,msg")]
+    fields: Vec<String>,
+
+    /// Highlight IPs, UUIDs, hashes, quoted strings, numbers with units,
+    /// paths and URLs
+    #[arg(long)]
+    semantic: bool,
+
+    /// Configuration file [default: ~/.config/logview/config.toml]
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Only parse lines in the named format
+    #[arg(long)]
+    format: Option<String>,
+
+    /// Pipe yanked lines to this command, e.g. `wl-copy`, instead of
+    /// copying them with OSC 52
+    #[arg(long)]
+    copy_command: Option<String>,
 }
 
 fn main() -> Result<(), Error> {
@@ -58,6 +114,29 @@ fn start(args: Args) -> Result<(), Error> {
         tui.set_history_path(p.into());
     }
 
+    tui.set_incremental(args.incremental);
+    tui.set_fields(args.fields.clone());
+    tui.set_semantic(args.semantic);
+    tui.set_copy_command(args.copy_command.clone());
+
+    let path = args.config.clone().or_else(Config::default_path);
+    let config = match &path {
+        Some(p) => Config::load(p)?,
+        None => Config::default(),
+    };
+    let mut formats = Formats::new(&config.formats)?;
+    if let Some(f) = &args.format {
+        formats.select(f)?;
+    }
+    tui.set_formats(formats);
+    tui.set_config(path, config.highlights);
+
+    let context = args.context.unwrap_or(0);
+    tui.set_context(
+        args.before_context.unwrap_or(context),
+        args.after_context.unwrap_or(context),
+    );
+
     if let Some(t) = &args.theme {
         tui.use_custom_theme(t)?;
     } else {
@@ -0,0 +1,108 @@
+/// A free-text note attached to a line.
+#[derive(Clone)]
+pub struct Note {
+    pub no: usize,
+    pub line: String,
+    pub text: String,
+}
+
+/// Notes on lines, ordered by line.
+#[derive(Clone, Default)]
+pub struct Notes {
+    notes: Vec<Note>,
+}
+
+impl Notes {
+    /// Attaches `text` to line `no`, replacing its note. An empty text
+    /// removes the note.
+    pub fn set(&mut self, no: usize, line: String, text: String) {
+        self.notes.retain(|n| n.no != no);
+        if !text.is_empty() {
+            let pos = self.notes.partition_point(|n| n.no < no);
+            self.notes.insert(pos, Note { no, line, text });
+        }
+    }
+
+    pub fn get(&self, no: usize) -> Option<&Note> {
+        self.notes.iter().find(|n| n.no == no)
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Renders a Markdown report with each note and up to `context` lines
+    /// around its line, as far as `lines` still has them.
+    pub fn report(&self, context: usize, lines: impl Fn(usize) -> Option<String>) -> String {
+        let mut report = String::from("# Incident notes\n");
+        for note in self.notes.iter() {
+            let mut block = String::new();
+            for no in note.no.saturating_sub(context)..note.no {
+                if let Some(line) = lines(no) {
+                    block.push_str(&format!("  {:>6}  {}\n", no + 1, line));
+                }
+            }
+            block.push_str(&format!("> {:>6}  {}\n", note.no + 1, note.line));
+            for no in note.no + 1..=note.no + context {
+                if let Some(line) = lines(no) {
+                    block.push_str(&format!("  {:>6}  {}\n", no + 1, line));
+                }
+            }
+            // longer than any run of backticks in the lines, so they can't close it
+            let fence = "`".repeat(Notes::longest_run(&block, '`').max(2) + 1);
+            report.push_str(&format!(
+                "\n## Line {}\n\n{}\n\n{}\n{}{}\n",
+                note.no + 1,
+                note.text,
+                fence,
+                block,
+                fence
+            ));
+        }
+        report
+    }
+
+    fn longest_run(s: &str, c: char) -> usize {
+        s.split(|x| x != c).map(str::len).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_notes_set_to_empty_text() {
+        let mut notes = Notes::default();
+        notes.set(4, "line".to_owned(), "first".to_owned());
+        notes.set(4, "line".to_owned(), "second".to_owned());
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes.get(4).map(|n| n.text.as_str()), Some("second"));
+        notes.set(4, "line".to_owned(), String::new());
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn reports_notes_with_context() {
+        let mut notes = Notes::default();
+        notes.set(2, "c".to_owned(), "broken here".to_owned());
+        let lines = ["a", "b", "c", "d"];
+        let report = notes.report(1, |no| lines.get(no).map(|s| s.to_string()));
+        assert_eq!(
+            report,
+            "# Incident notes\n\n## Line 3\n\nbroken here\n\n```\n       2  b\n>      3  c\n       4  d\n```\n"
+        );
+    }
+
+    #[test]
+    fn fences_lines_with_backticks() {
+        let mut notes = Notes::default();
+        notes.set(0, "run ```` here".to_owned(), "odd".to_owned());
+        let report = notes.report(0, |_| None);
+        assert!(report.contains("\n`````\n>      1  run ```` here\n`````\n"));
+    }
+}
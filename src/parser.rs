@@ -1,9 +1,10 @@
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::character::complete::digit1;
-use nom::combinator::opt;
+use nom::bytes::complete::{escaped_transform, is_not, tag, take_till};
+use nom::character::complete::{char, digit1, multispace1};
+use nom::combinator::{all_consuming, map, opt, value};
 use nom::error::ParseError;
-use nom::sequence::tuple;
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, pair, preceded, tuple};
 use nom::IResult;
 
 use std::str::FromStr;
@@ -12,6 +13,8 @@ use cursive::theme::BaseColor;
 use cursive::theme::Color as CursiveColor;
 use cursive::theme::ColorType;
 
+use crate::fields::Fields;
+
 type ColorTuple = (Option<ColorMode>, Option<ColorMode>, Option<u8>);
 
 pub enum ColorMode {
@@ -44,11 +47,11 @@ impl ColorMode {
     }
 }
 
-impl Into<ColorType> for ColorMode {
-    fn into(self) -> ColorType {
-        match self {
+impl From<ColorMode> for ColorType {
+    fn from(mode: ColorMode) -> ColorType {
+        match mode {
             ColorMode::Default => ColorType::InheritParent,
-            ColorMode::Base16(v) => ColorType::Color(Self::u8_into_color(v)),
+            ColorMode::Base16(v) => ColorType::Color(ColorMode::u8_into_color(v)),
             ColorMode::Base256(c) => ColorType::Color(c.into()),
         }
     }
@@ -59,9 +62,9 @@ pub enum Color {
     Rgb((u8, u8, u8)),
 }
 
-impl Into<CursiveColor> for Color {
-    fn into(self) -> CursiveColor {
-        match self {
+impl From<Color> for CursiveColor {
+    fn from(color: Color) -> CursiveColor {
+        match color {
             Color::Base(v) => CursiveColor::from_256colors(v),
             Color::Rgb((r, g, b)) => CursiveColor::Rgb(r, g, b),
         }
@@ -186,3 +189,157 @@ impl ColorParser {
         .map(|(r, (_, fg, _, bg, _, sp, _))| (r, (fg, bg, sp)))
     }
 }
+
+pub struct JsonParser {}
+
+impl JsonParser {
+    /// Parses a JSON object into its fields. Nested objects are flattened
+    /// into dotted keys, all other values are kept in their JSON form.
+    pub fn parse(s: &str) -> Option<Fields> {
+        let s = s.trim();
+        if !s.starts_with('{') {
+            return None;
+        }
+        match serde_json::from_str::<serde_json::Value>(s) {
+            Ok(serde_json::Value::Object(map)) => {
+                let mut fields = Fields::default();
+                Self::flatten("", &map, &mut fields);
+                Some(fields)
+            }
+            _ => None,
+        }
+    }
+
+    fn flatten(
+        prefix: &str,
+        map: &serde_json::Map<String, serde_json::Value>,
+        fields: &mut Fields,
+    ) {
+        for (key, value) in map.iter() {
+            let key = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            match value {
+                serde_json::Value::Object(map) => Self::flatten(&key, map, fields),
+                serde_json::Value::String(s) => fields.push(key, s.clone()),
+                value => fields.push(key, value.to_string()),
+            }
+        }
+    }
+}
+
+pub struct LogfmtParser {}
+
+impl LogfmtParser {
+    fn key(s: &str) -> IResult<&str, &str> {
+        is_not(" \t=\"")(s)
+    }
+
+    fn quoted(s: &str) -> IResult<&str, String> {
+        delimited(
+            char('"'),
+            map(
+                opt(escaped_transform(
+                    is_not("\\\""),
+                    '\\',
+                    alt((
+                        value("\\", tag("\\")),
+                        value("\"", tag("\"")),
+                        value("\n", tag("n")),
+                        value("\r", tag("r")),
+                        value("\t", tag("t")),
+                    )),
+                )),
+                Option::unwrap_or_default,
+            ),
+            char('"'),
+        )(s)
+    }
+
+    fn unquoted(s: &str) -> IResult<&str, String> {
+        map(
+            take_till(|c: char| c.is_whitespace() || c == '"'),
+            str::to_owned,
+        )(s)
+    }
+
+    /// A `key=value` pair or a bare key without a value.
+    fn pair(s: &str) -> IResult<&str, (&str, Option<String>)> {
+        pair(
+            Self::key,
+            opt(preceded(char('='), alt((Self::quoted, Self::unquoted)))),
+        )(s)
+    }
+
+    /// Parses a line of `key=value` pairs, where bare keys get an empty
+    /// value. Lines with less than two pairs aren't considered logfmt to
+    /// avoid matching plain text.
+    pub fn parse(s: &str) -> Option<Fields> {
+        match all_consuming(separated_list1(multispace1, Self::pair))(s.trim()) {
+            Ok((_, pairs)) if pairs.iter().filter(|(_, v)| v.is_some()).count() >= 2 => {
+                let mut fields = Fields::default();
+                for (key, value) in pairs.into_iter() {
+                    fields.push(key.to_owned(), value.unwrap_or_default());
+                }
+                Some(fields)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(s: &str) -> Option<Vec<(String, String)>> {
+        LogfmtParser::parse(s).map(|f| f.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+    }
+
+    fn owned(pairs: &[(&str, &str)]) -> Option<Vec<(String, String)>> {
+        Some(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn parses_quoted_values() {
+        assert_eq!(
+            pairs(r#"level=info msg="request done" path=/"#),
+            owned(&[("level", "info"), ("msg", "request done"), ("path", "/")])
+        );
+        assert_eq!(pairs(r#"a="" b=1"#), owned(&[("a", ""), ("b", "1")]));
+    }
+
+    #[test]
+    fn unescapes_quoted_values() {
+        assert_eq!(
+            pairs(r#"msg="say \"hi\"" path="C:\\tmp""#),
+            owned(&[("msg", r#"say "hi""#), ("path", r"C:\tmp")])
+        );
+        assert_eq!(
+            pairs(r#"msg="a\tb\nc" n=1"#),
+            owned(&[("msg", "a\tb\nc"), ("n", "1")])
+        );
+    }
+
+    #[test]
+    fn accepts_bare_keys() {
+        assert_eq!(
+            pairs("a=1 flag b=2"),
+            owned(&[("a", "1"), ("flag", ""), ("b", "2")])
+        );
+    }
+
+    #[test]
+    fn rejects_plain_text() {
+        assert_eq!(pairs("just some words"), None);
+        assert_eq!(pairs("one=pair and words"), None);
+        assert_eq!(pairs(r#"a="unterminated b=2"#), None);
+    }
+}
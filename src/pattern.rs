@@ -0,0 +1,167 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use regex::{Regex, RegexBuilder};
+
+use crate::error::Error;
+use crate::fields::{FieldFilter, Fields};
+use crate::timestamp::TimeRange;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Case {
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+/// Options applied to every pattern typed into the command line.
+#[derive(Clone, Copy)]
+pub struct Flags {
+    pub case: Case,
+    pub literal: bool,
+    pub word: bool,
+    pub fuzzy: bool,
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Self {
+            case: Case::Smart,
+            literal: false,
+            word: false,
+            fuzzy: false,
+        }
+    }
+}
+
+impl Flags {
+    pub fn compile(&self, pattern: &str) -> Result<Regex, regex::Error> {
+        let mut expr = if self.fuzzy {
+            pattern
+                .chars()
+                .map(|c| regex::escape(c.encode_utf8(&mut [0; 4])))
+                .collect::<Vec<_>>()
+                .join(".*?")
+        } else if self.literal {
+            regex::escape(pattern)
+        } else {
+            pattern.to_owned()
+        };
+        if self.word {
+            expr = format!(r"\b(?:{})\b", expr);
+        }
+        let insensitive = match self.case {
+            Case::Smart => !pattern.chars().any(char::is_uppercase),
+            Case::Sensitive => false,
+            Case::Insensitive => true,
+        };
+        RegexBuilder::new(&expr)
+            .case_insensitive(insensitive)
+            .build()
+    }
+
+    pub fn next_case(&mut self) {
+        self.case = match self.case {
+            Case::Smart => Case::Sensitive,
+            Case::Sensitive => Case::Insensitive,
+            Case::Insensitive => Case::Smart,
+        };
+    }
+
+    pub fn indicators(&self) -> String {
+        let mut s = String::from(match self.case {
+            Case::Smart => " [smart-case]",
+            Case::Sensitive => " [case]",
+            Case::Insensitive => " [nocase]",
+        });
+        if self.literal {
+            s.push_str(" [literal]");
+        }
+        if self.word {
+            s.push_str(" [word]");
+        }
+        if self.fuzzy {
+            s.push_str(" [fuzzy]");
+        }
+        s
+    }
+}
+
+enum Pattern {
+    Regex(Regex),
+    Field(FieldFilter),
+}
+
+/// The active filter, patterns on the whole line or conditions on parsed
+/// fields joined by `&&`, limited to a time range.
+pub struct Filter {
+    patterns: Vec<Pattern>,
+    range: TimeRange,
+}
+
+impl Filter {
+    /// Creates the filter, with times of day on the date of `now`.
+    pub fn new(filter: &str, flags: &Flags, now: NaiveDateTime) -> Result<Filter, Error> {
+        let (range, rest) = TimeRange::extract(filter, now)?;
+        let patterns = rest
+            .split("&&")
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(|term| {
+                match FieldFilter::parse(term) {
+                    Some(f) => f.map(Pattern::Field),
+                    None => flags.compile(term).map(Pattern::Regex),
+                }
+                .map_err(Error::InvalidPattern)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Filter { patterns, range })
+    }
+
+    /// Matches a line with its time and parsed fields.
+    pub fn is_match(
+        &self,
+        line: &str,
+        time: Option<DateTime<Utc>>,
+        fields: Option<&Fields>,
+    ) -> bool {
+        self.range.contains(time)
+            && self.patterns.iter().all(|p| match p {
+                Pattern::Regex(r) => r.is_match(line),
+                Pattern::Field(f) => fields.is_some_and(|fields| f.matches(fields)),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> Fields {
+        let mut fields = Fields::default();
+        for (k, v) in pairs.iter() {
+            fields.push(k.to_string(), v.to_string());
+        }
+        fields
+    }
+
+    #[test]
+    fn joins_patterns_and_field_conditions() {
+        let now = chrono::Local::now().naive_local();
+        let filter = Filter::new("timeout && .level == warn", &Flags::default(), now).unwrap();
+        let warn = fields(&[("level", "warn")]);
+        let info = fields(&[("level", "info")]);
+        assert!(filter.is_match("db timeout", None, Some(&warn)));
+        assert!(!filter.is_match("db timeout", None, Some(&info)));
+        assert!(!filter.is_match("db ready", None, Some(&warn)));
+        assert!(!filter.is_match("db timeout", None, None));
+    }
+
+    #[test]
+    fn keeps_time_terms_out_of_the_conditions() {
+        let now = chrono::Local::now().naive_local();
+        let filter = Filter::new("last:1h && .level == warn", &Flags::default(), now).unwrap();
+        let warn = fields(&[("level", "warn")]);
+        assert!(filter.is_match("x", Some(Utc::now()), Some(&warn)));
+        assert!(!filter.is_match("x", None, Some(&warn)));
+        assert!(Filter::new("ok && (", &Flags::default(), now).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use regex::Regex;
+
+/// The kinds of tokens recognized by the built-in highlighters.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Class {
+    Ip,
+    Uuid,
+    Hash,
+    Quoted,
+    Number,
+    Path,
+    Url,
+}
+
+impl Class {
+    /// Later classes are drawn over earlier ones, so URLs win over paths.
+    pub const ALL: [Class; 7] = [
+        Class::Ip,
+        Class::Uuid,
+        Class::Hash,
+        Class::Quoted,
+        Class::Number,
+        Class::Path,
+        Class::Url,
+    ];
+
+    /// Theme color key of the class.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Class::Ip => "semantic_ip",
+            Class::Uuid => "semantic_uuid",
+            Class::Hash => "semantic_hash",
+            Class::Quoted => "semantic_quoted",
+            Class::Number => "semantic_number",
+            Class::Path => "semantic_path",
+            Class::Url => "semantic_url",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Class::Ip => "IP addresses",
+            Class::Uuid => "UUIDs",
+            Class::Hash => "Hashes",
+            Class::Quoted => "Quoted strings",
+            Class::Number => "Numbers with units",
+            Class::Path => "File paths",
+            Class::Url => "URLs",
+        }
+    }
+
+    pub fn regex(&self) -> Regex {
+        let pattern = match self {
+            Class::Ip => concat!(
+                r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b",
+                r"|\b(?:[0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}\b",
+                r"|\b(?:[0-9a-fA-F]{1,4}:){1,6}(?::[0-9a-fA-F]{1,4}){1,6}\b",
+                r"|::1\b",
+            ),
+            Class::Uuid => {
+                r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b"
+            }
+            Class::Hash => r"\b(?:[0-9a-fA-F]{64}|[0-9a-fA-F]{40}|[0-9a-fA-F]{32})\b",
+            Class::Quoted => r#""(?:[^"\\]|\\.)*""#,
+            Class::Number => r"\b\d+(?:\.\d+)?(?:(?:ns|us|µs|ms|s|m|h|d|[kKMGT]i?B|B)\b|%)",
+            Class::Path => r"\B(?:~|\.{1,2})?/[\w.\-]+(?:/[\w.\-]+)*/?",
+            Class::Url => r"\b[a-zA-Z][a-zA-Z0-9+.\-]*://[^\s\x22'<>]+",
+        };
+        Regex::new(pattern).unwrap()
+    }
+}
@@ -1,5 +1,22 @@
 use crate::error::Error;
 use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[derive(Clone, Copy)]
+pub enum SourceState {
+    Following,
+    Eof,
+}
+
+impl SourceState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SourceState::Following => "following",
+            SourceState::Eof => "EOF",
+        }
+    }
+}
 
 pub struct Source<A: Send> {
     handle: Box<dyn TryRead<A> + 'static + Send>,
@@ -17,16 +34,25 @@ impl<A: Send> TryRead<A> for Source<A> {
     fn try_read(&self) -> Option<A> {
         self.handle.try_read()
     }
+
+    fn state(&self) -> SourceState {
+        self.handle.state()
+    }
 }
 
 pub trait TryRead<A> {
     fn try_read(&self) -> Option<A>;
+
+    fn state(&self) -> SourceState {
+        SourceState::Following
+    }
 }
 
 pub struct AsyncPipeIn {
     handle: Option<std::thread::JoinHandle<()>>,
     terminate: std::sync::mpsc::Sender<()>,
     recv: std::sync::mpsc::Receiver<String>,
+    eof: Arc<AtomicBool>,
 }
 
 impl Drop for AsyncPipeIn {
@@ -41,16 +67,19 @@ impl AsyncPipeIn {
         if !atty::is(atty::Stream::Stdin) {
             let (tx, rx) = std::sync::mpsc::channel();
             let (ty, ry) = std::sync::mpsc::channel();
+            let eof = Arc::new(AtomicBool::new(false));
+            let closed = eof.clone();
             Ok(AsyncPipeIn {
                 handle: Some(std::thread::spawn(move || {
                     let stdin = std::io::stdin();
                     let mut stdin = stdin.lock();
                     let mut buf = String::with_capacity(1024);
                     loop {
-                        if stdin.read_line(&mut buf).is_ok()
-                            && !buf.is_empty()
-                            && tx.send(buf.clone()).is_err()
-                        {
+                        let read = stdin.read_line(&mut buf);
+                        if let Ok(0) = read {
+                            closed.store(true, Ordering::Relaxed);
+                        }
+                        if read.is_ok() && !buf.is_empty() && tx.send(buf.clone()).is_err() {
                             break;
                         }
                         match ry.try_recv() {
@@ -62,6 +91,7 @@ impl AsyncPipeIn {
                 })),
                 terminate: ty,
                 recv: rx,
+                eof,
             })
         } else {
             Err(Error::NoPipeIn)
@@ -71,10 +101,13 @@ impl AsyncPipeIn {
 
 impl TryRead<String> for AsyncPipeIn {
     fn try_read(&self) -> Option<String> {
-        if let Ok(s) = self.recv.try_recv() {
-            Some(s)
-        } else {
-            None
+        self.recv.try_recv().ok()
+    }
+
+    fn state(&self) -> SourceState {
+        match self.eof.load(Ordering::Relaxed) {
+            true => SourceState::Eof,
+            false => SourceState::Following,
         }
     }
 }
@@ -129,10 +162,6 @@ impl AsyncFileIn {
 
 impl TryRead<String> for AsyncFileIn {
     fn try_read(&self) -> Option<String> {
-        if let Ok(s) = self.recv.try_recv() {
-            Some(s)
-        } else {
-            None
-        }
+        self.recv.try_recv().ok()
     }
 }
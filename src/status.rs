@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+use crate::buffer::SourceBuffer;
+
+const RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Counters of the content view shown in the status bar.
+pub struct Status {
+    pub matched: usize,
+    pub hidden: usize,
+    pub shown: usize,
+    pub filtering: bool,
+    /// New lines held back while the view is paused.
+    pub held: Option<usize>,
+    rate: usize,
+    last: (usize, Instant),
+}
+
+impl Status {
+    pub fn new() -> Self {
+        Self {
+            matched: 0,
+            hidden: 0,
+            shown: 0,
+            filtering: false,
+            held: None,
+            rate: 0,
+            last: (0, Instant::now()),
+        }
+    }
+
+    /// Resets the counters of the content view before it's replayed.
+    pub fn reset(&mut self) {
+        self.matched = 0;
+        self.hidden = 0;
+        self.shown = 0;
+    }
+
+    /// Measures the input rate once per interval. Returns `true` if the
+    /// rate was measured, i.e. the status has to be refreshed.
+    pub fn tick(&mut self, count: usize) -> bool {
+        let elapsed = self.last.1.elapsed();
+        if elapsed < RATE_INTERVAL {
+            return false;
+        }
+        self.rate = ((count - self.last.0) as f64 / elapsed.as_secs_f64()).round() as usize;
+        self.last = (count, Instant::now());
+        true
+    }
+
+    pub fn text<A: Send + Clone>(&self, buffer: &SourceBuffer<A>) -> String {
+        let mut text = format!(
+            "{} received  {} buffered  {} evicted  {} shown",
+            buffer.count(),
+            buffer.len(),
+            buffer.first(),
+            self.shown
+        );
+        if self.filtering {
+            text.push_str(&format!("  {} matching", self.matched));
+        }
+        if self.hidden > 0 {
+            text.push_str(&format!("  {} muted", self.hidden));
+        }
+        text.push_str(&format!("  {}/s  [{}]", self.rate, buffer.state().name()));
+        if let Some(held) = self.held {
+            text.push_str(&format!("  [paused, {} new lines below]", held));
+        }
+        text
+    }
+}
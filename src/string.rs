@@ -1,5 +1,7 @@
 use cursive::theme::{ColorStyle, ColorType, Effect, Style};
 use cursive::utils::span::SpannedString;
+use regex::Regex;
+use std::ops::Range;
 
 use crate::parser::{ColorMode, ColorParser};
 
@@ -27,8 +29,9 @@ impl ColoredString {
                     input = r;
                 }
                 Err(_) => {
-                    input = &input[1..];
-                    end += 1;
+                    let len = input.chars().next().map_or(1, char::len_utf8);
+                    input = &input[len..];
+                    end += len;
                 }
             }
         }
@@ -59,8 +62,9 @@ impl ColoredString {
                     input = r;
                 }
                 Err(_) => {
-                    input = &input[1..];
-                    end += 1;
+                    let len = input.chars().next().map_or(1, char::len_utf8);
+                    input = &input[len..];
+                    end += len;
                 }
             }
         }
@@ -70,6 +74,106 @@ impl ColoredString {
         Self::create_styled_string(values)
     }
 
+    pub fn highlight(s: SpannedString<Style>, rules: &[(Regex, Style)]) -> SpannedString<Style> {
+        let marks: Vec<(usize, usize, Style)> = rules
+            .iter()
+            .flat_map(|(r, style)| {
+                r.find_iter(s.source())
+                    .map(move |m| (m.start(), m.end(), *style))
+            })
+            .filter(|(start, end, _)| start < end)
+            .collect();
+        if marks.is_empty() {
+            return s;
+        }
+
+        let mut output = SpannedString::new();
+        for span in s.spans_raw() {
+            let (start, end) = match span.content.as_borrowed() {
+                Some(v) => v,
+                None => {
+                    output.append(SpannedString::styled(
+                        span.content.resolve(s.source()),
+                        span.attr,
+                    ));
+                    continue;
+                }
+            };
+            let mut bounds = vec![start, end];
+            for (ms, me, _) in marks.iter() {
+                bounds.extend([*ms, *me].iter().filter(|b| start < **b && **b < end));
+            }
+            bounds.sort_unstable();
+            bounds.dedup();
+            for w in bounds.windows(2) {
+                let style = marks
+                    .iter()
+                    .filter(|(ms, me, _)| *ms <= w[0] && w[1] <= *me)
+                    .fold(span.attr, |acc, (_, _, st)| acc.combine(*st));
+                output.append(SpannedString::styled(&s.source()[w[0]..w[1]], style));
+            }
+        }
+        output
+    }
+
+    /// Replaces `range` of the source with `with`, styled like the first
+    /// replaced character.
+    pub fn replace(
+        s: SpannedString<Style>,
+        range: Range<usize>,
+        with: &str,
+    ) -> SpannedString<Style> {
+        let mut output = SpannedString::new();
+        let mut replaced = false;
+        for span in s.spans_raw() {
+            let (start, end) = match span.content.as_borrowed() {
+                Some(v) => v,
+                None => {
+                    output.append(SpannedString::styled(
+                        span.content.resolve(s.source()),
+                        span.attr,
+                    ));
+                    continue;
+                }
+            };
+            if start < range.start {
+                output.append(SpannedString::styled(
+                    &s.source()[start..end.min(range.start)],
+                    span.attr,
+                ));
+            }
+            if !replaced && start <= range.start && range.start < end {
+                output.append(SpannedString::styled(with, span.attr));
+                replaced = true;
+            }
+            if range.end < end {
+                output.append(SpannedString::styled(
+                    &s.source()[start.max(range.end)..end],
+                    span.attr,
+                ));
+            }
+        }
+        output
+    }
+
+    pub fn strip(s: &str) -> String {
+        Self::plain(s).source().to_owned()
+    }
+
+    pub fn tint(mut s: SpannedString<Style>, style: Style) -> SpannedString<Style> {
+        for span in s.spans_raw_attr_mut() {
+            *span.attr = style.combine(*span.attr);
+        }
+        s
+    }
+
+    pub fn dim(mut s: SpannedString<Style>) -> SpannedString<Style> {
+        for span in s.spans_raw_attr_mut() {
+            *span.attr = span.attr.combine(Effect::Dim);
+        }
+        s
+    }
+
     fn create_style(fg: Option<ColorMode>, bg: Option<ColorMode>, sp: Option<u8>) -> Style {
         let mut fg = match fg {
             Some(v) => v.into(),
@@ -119,3 +223,21 @@ impl ColoredString {
         output
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_non_ascii_lines() {
+        assert_eq!(ColoredString::strip("café ok"), "café ok");
+        assert_eq!(ColoredString::strip("\x1b[31mgrüße\x1b[0m ✓"), "grüße ✓");
+    }
+
+    #[test]
+    fn styles_non_ascii_lines() {
+        let styled = ColoredString::styled("\x1b[1mnaïve\x1b[0m 日本");
+        assert_eq!(styled.source(), "naïve 日本");
+        assert_eq!(ColoredString::plain("日本").source(), "日本");
+    }
+}
@@ -0,0 +1,204 @@
+use cursive::theme::{Effect, Style};
+use cursive::utils::span::SpannedString;
+use std::cmp::Ordering;
+
+use crate::fields::{Fields, MAX_COLUMN_WIDTH};
+
+struct Column {
+    name: String,
+    width: usize,
+    hidden: bool,
+}
+
+/// A snapshot of structured lines shown as a table, with each row keeping
+/// the number of its line.
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<(usize, Fields)>,
+    selected: usize,
+    sort: Option<(usize, bool)>,
+}
+
+impl Table {
+    /// Creates the table with the given columns first, followed by the
+    /// other fields in order of appearance.
+    pub fn new(rows: Vec<(usize, Fields)>, names: &[String]) -> Self {
+        let mut columns: Vec<Column> = Vec::new();
+        let found = rows
+            .iter()
+            .flat_map(|(_, fields)| fields.iter().map(|(k, _)| k));
+        let preferred = names
+            .iter()
+            .filter(|n| rows.iter().any(|(_, f)| f.get(n).is_some()));
+        for name in preferred.chain(found) {
+            if columns.iter().any(|c| &c.name == name) {
+                continue;
+            }
+            let width = rows
+                .iter()
+                .filter_map(|(_, f)| f.get(name))
+                .map(|v| v.chars().count())
+                .fold(name.chars().count() + 1, usize::max);
+            columns.push(Column {
+                name: name.clone(),
+                width: width.min(MAX_COLUMN_WIDTH),
+                hidden: false,
+            });
+        }
+        Self {
+            columns,
+            rows,
+            selected: 0,
+            sort: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn header(&self) -> SpannedString<Style> {
+        let mut output = SpannedString::new();
+        let visible = self.visible();
+        for (n, i) in visible.iter().enumerate() {
+            let column = &self.columns[*i];
+            let mut name = column.name.clone();
+            match self.sort {
+                Some((s, false)) if s == *i => name.push('▲'),
+                Some((s, true)) if s == *i => name.push('▼'),
+                _ => {}
+            }
+            let style = match *i == self.selected {
+                true => Style::from(Effect::Reverse).combine(Effect::Bold),
+                false => Style::from(Effect::Bold),
+            };
+            output.append(SpannedString::styled(
+                Table::cell(&name, column.width, n + 1 == visible.len()),
+                style,
+            ));
+        }
+        output
+    }
+
+    /// Returns the rendered rows with their line numbers.
+    pub fn rows(&self) -> Vec<(String, usize)> {
+        let visible = self.visible();
+        self.rows
+            .iter()
+            .map(|(no, fields)| {
+                let mut row = String::new();
+                for (n, i) in visible.iter().enumerate() {
+                    let column = &self.columns[*i];
+                    let value = fields.get(&column.name).unwrap_or("");
+                    row.push_str(&Table::cell(value, column.width, n + 1 == visible.len()));
+                }
+                (row, *no)
+            })
+            .collect()
+    }
+
+    /// Moves the column selection by `delta` visible columns.
+    pub fn select(&mut self, delta: isize) {
+        let visible = self.visible();
+        if let Some(pos) = visible.iter().position(|i| *i == self.selected) {
+            let pos = pos.saturating_add_signed(delta).min(visible.len() - 1);
+            self.selected = visible[pos];
+        }
+    }
+
+    pub fn resize(&mut self, delta: isize) {
+        if let Some(c) = self.columns.get_mut(self.selected) {
+            c.width = c.width.saturating_add_signed(delta).max(1);
+        }
+    }
+
+    /// Swaps the selected column with its visible neighbour.
+    pub fn shift(&mut self, delta: isize) {
+        let visible = self.visible();
+        let pos = match visible.iter().position(|i| *i == self.selected) {
+            Some(pos) => pos,
+            None => return,
+        };
+        let other = match visible.get(pos.wrapping_add_signed(delta)) {
+            Some(other) => *other,
+            None => return,
+        };
+        self.columns.swap(self.selected, other);
+        self.sort = self.sort.map(|(s, desc)| match s {
+            s if s == self.selected => (other, desc),
+            s if s == other => (self.selected, desc),
+            s => (s, desc),
+        });
+        self.selected = other;
+    }
+
+    /// Hides the selected column unless it is the last visible one.
+    pub fn hide(&mut self) {
+        if self.visible().len() > 1 {
+            self.columns[self.selected].hidden = true;
+            let visible = self.visible();
+            self.selected = visible
+                .iter()
+                .find(|i| **i > self.selected)
+                .or(visible.last())
+                .copied()
+                .unwrap_or(0);
+        }
+    }
+
+    pub fn show_all(&mut self) {
+        for c in self.columns.iter_mut() {
+            c.hidden = false;
+        }
+    }
+
+    /// Sorts by the selected column, reversing the order if it's already
+    /// sorted by it. Numbers are compared by value and missing values come
+    /// last.
+    pub fn sort(&mut self) {
+        let descending = self.sort == Some((self.selected, false));
+        self.sort = Some((self.selected, descending));
+        let name = match self.columns.get(self.selected) {
+            Some(c) => c.name.clone(),
+            None => return,
+        };
+        self.rows
+            .sort_by(|(_, a), (_, b)| match (a.get(&name), b.get(&name)) {
+                (Some(a), Some(b)) => {
+                    let order = match (a.parse::<f64>(), b.parse::<f64>()) {
+                        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                        _ => a.cmp(b),
+                    };
+                    if descending {
+                        order.reverse()
+                    } else {
+                        order
+                    }
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+    }
+
+    fn visible(&self) -> Vec<usize> {
+        (0..self.columns.len())
+            .filter(|i| !self.columns[*i].hidden)
+            .collect()
+    }
+
+    /// Pads or truncates a value to the column width. The last column is
+    /// shown in full.
+    fn cell(value: &str, width: usize, last: bool) -> String {
+        let len = value.chars().count();
+        if last {
+            value.to_owned()
+        } else if len > width {
+            let mut cell: String = value.chars().take(width.saturating_sub(1)).collect();
+            cell.push_str("… ");
+            cell
+        } else {
+            format!("{}{} ", value, " ".repeat(width - len))
+        }
+    }
+}
@@ -0,0 +1,548 @@
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use regex::{Captures, Regex};
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use crate::error::Error;
+use crate::fields::Fields;
+
+const TIME_FIELDS: [&str; 4] = ["ts", "time", "timestamp", "@timestamp"];
+/// Epoch seconds from 2000-01-01 to 2100-01-01.
+const EPOCH_RANGE: Range<i64> = 946_684_800..4_102_444_800;
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+pub struct Timestamp {}
+
+impl Timestamp {
+    /// Finds the timestamp at the start of a line without escape sequences
+    /// and returns it with its byte range.
+    ///
+    /// Recognizes ISO 8601 and RFC 3339 (`2024-01-02T10:00:00.123Z`),
+    /// syslog (`Jan  2 10:00:00`), epoch seconds and milliseconds and
+    /// `10:00:00.123`, optionally inside brackets.
+    pub fn detect(line: &str) -> Option<(DateTime<Utc>, Range<usize>)> {
+        let start = if line.starts_with('[') { 1 } else { 0 };
+        let (time, len) = Timestamp::prefix(&line[start..])?;
+        Some((time, start..start + len))
+    }
+
+    /// Finds the timestamp field of a structured line.
+    pub fn field(fields: &Fields) -> Option<(&'static str, DateTime<Utc>)> {
+        TIME_FIELDS
+            .iter()
+            .find_map(|k| fields.get(k).and_then(Timestamp::parse).map(|t| (*k, t)))
+    }
+
+    /// Parses a timestamp field, which also accepts the access log format
+    /// (`10/Oct/2000:13:55:36 -0700`).
+    pub fn parse(s: &str) -> Option<DateTime<Utc>> {
+        static ACCESS: OnceLock<Regex> = OnceLock::new();
+
+        let access = ACCESS.get_or_init(|| {
+            Regex::new(r"^(\d{2})/([A-Z][a-z]{2})/(\d{4}):(\d{2}):(\d{2}):(\d{2}) ([+-]\d{4})$")
+                .unwrap()
+        });
+        if let Some(c) = access.captures(s) {
+            let month = Timestamp::month(&c[2])?;
+            return Timestamp::build(&c, (3, month, 1), 4, None, Some(7));
+        }
+        Timestamp::prefix(s)
+            .filter(|(_, len)| *len == s.len())
+            .map(|(time, _)| time)
+    }
+
+    fn prefix(s: &str) -> Option<(DateTime<Utc>, usize)> {
+        static ISO: OnceLock<Regex> = OnceLock::new();
+        static SYSLOG: OnceLock<Regex> = OnceLock::new();
+        static EPOCH: OnceLock<Regex> = OnceLock::new();
+        static CLOCK: OnceLock<Regex> = OnceLock::new();
+
+        let iso = ISO.get_or_init(|| {
+            Regex::new(
+                r"^(\d{4})[-/](\d{2})[-/](\d{2})[T ](\d{2}):(\d{2}):(\d{2})(?:[.,](\d{1,9})\d*)?(Z|[+-]\d{2}:?\d{2})?",
+            )
+            .unwrap()
+        });
+        if let Some(c) = iso.captures(s) {
+            let month = c[2].parse().ok()?;
+            let time = Timestamp::build(&c, (1, month, 3), 4, Some(7), Some(8))?;
+            return Some((time, c[0].len()));
+        }
+        let syslog = SYSLOG.get_or_init(|| {
+            Regex::new(r"^([A-Z][a-z]{2}) ([ \d]\d) (\d{2}):(\d{2}):(\d{2})(?:\.(\d{1,9})\d*)?")
+                .unwrap()
+        });
+        if let Some(c) = syslog.captures(s) {
+            let date = NaiveDate::from_ymd_opt(
+                Local::now().year(),
+                Timestamp::month(&c[1])?,
+                c[2].trim().parse().ok()?,
+            )?;
+            let time = Timestamp::local(date, &c, 3, Some(6))?;
+            return Some((time, c[0].len()));
+        }
+        let epoch =
+            EPOCH.get_or_init(|| Regex::new(r"^(\d{10}|\d{13})(?:\.(\d{1,9})\d*)?\b").unwrap());
+        if let Some(c) = epoch.captures(s).filter(|c| Timestamp::is_epoch(&c[1])) {
+            let n: i64 = c[1].parse().ok()?;
+            let time = if c[1].len() == 13 {
+                Utc.timestamp_millis_opt(n).single()?
+            } else {
+                Utc.timestamp_opt(n, Timestamp::nanos(c.get(2).map(|m| m.as_str())))
+                    .single()?
+            };
+            return Some((time, c[0].len()));
+        }
+        let clock = CLOCK.get_or_init(|| {
+            Regex::new(r"^(\d{2}):(\d{2}):(\d{2})(?:[.,](\d{1,9})\d*)?\b").unwrap()
+        });
+        if let Some(c) = clock.captures(s) {
+            let time = Timestamp::local(Local::now().date_naive(), &c, 1, Some(4))?;
+            return Some((time, c[0].len()));
+        }
+        None
+    }
+
+    /// Builds a time from the year and day groups, the month, the groups
+    /// of hours, minutes and seconds starting at `hms`, and the optional
+    /// fraction and offset groups. Times without an offset are local.
+    fn build(
+        c: &Captures,
+        (year, month, day): (usize, u32, usize),
+        hms: usize,
+        frac: Option<usize>,
+        offset: Option<usize>,
+    ) -> Option<DateTime<Utc>> {
+        let date = NaiveDate::from_ymd_opt(c[year].parse().ok()?, month, c[day].parse().ok()?)?;
+        match offset.and_then(|i| c.get(i)).map(|m| m.as_str()) {
+            None => Timestamp::local(date, c, hms, frac),
+            Some(offset) => {
+                let time = Timestamp::time(date, c, hms, frac)?;
+                let seconds = match offset {
+                    "Z" => 0,
+                    _ => {
+                        let digits = offset[1..].replace(':', "");
+                        let hours: i64 = digits[..2].parse().ok()?;
+                        let minutes: i64 = digits[2..].parse().ok()?;
+                        let seconds = hours * 3600 + minutes * 60;
+                        if offset.starts_with('-') {
+                            -seconds
+                        } else {
+                            seconds
+                        }
+                    }
+                };
+                Some(Utc.from_utc_datetime(&time) - Duration::seconds(seconds))
+            }
+        }
+    }
+
+    fn local(
+        date: NaiveDate,
+        c: &Captures,
+        hms: usize,
+        frac: Option<usize>,
+    ) -> Option<DateTime<Utc>> {
+        let time = Timestamp::time(date, c, hms, frac)?;
+        Local
+            .from_local_datetime(&time)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    fn time(
+        date: NaiveDate,
+        c: &Captures,
+        hms: usize,
+        frac: Option<usize>,
+    ) -> Option<chrono::NaiveDateTime> {
+        date.and_hms_nano_opt(
+            c[hms].parse().ok()?,
+            c[hms + 1].parse().ok()?,
+            c[hms + 2].parse().ok()?,
+            Timestamp::nanos(frac.and_then(|i| c.get(i)).map(|m| m.as_str())),
+        )
+    }
+
+    /// Only takes numbers as epoch times if their seconds fall within
+    /// `EPOCH_RANGE`, so IDs and counters at the start of a line aren't.
+    fn is_epoch(digits: &str) -> bool {
+        digits[..10]
+            .parse()
+            .is_ok_and(|seconds: i64| EPOCH_RANGE.contains(&seconds))
+    }
+
+    fn nanos(frac: Option<&str>) -> u32 {
+        frac.map_or(0, |f| format!("{:0<9}", f).parse().unwrap_or(0))
+    }
+
+    fn month(name: &str) -> Option<u32> {
+        MONTHS.iter().position(|m| *m == name).map(|i| i as u32 + 1)
+    }
+}
+
+/// A point in time given by the user, either absolute or a time of day
+/// to be resolved on a date.
+#[derive(Clone, Copy)]
+pub enum Bound {
+    At(DateTime<Utc>),
+    Clock(NaiveTime),
+}
+
+impl Bound {
+    /// Parses a time of day (`10:42`, `10:42:30.5`) or a full timestamp.
+    /// A time of day without seconds ends at the end of the minute if
+    /// `end` is set.
+    pub fn parse(s: &str, end: bool) -> Result<Self, Error> {
+        if let Ok(t) = NaiveTime::parse_from_str(s, "%H:%M:%S%.f") {
+            return Ok(Bound::Clock(t));
+        }
+        if let Ok(t) = NaiveTime::parse_from_str(s, "%H:%M") {
+            return Ok(match end {
+                true => Bound::Clock(t + Duration::milliseconds(59_999)),
+                false => Bound::Clock(t),
+            });
+        }
+        Timestamp::parse(s)
+            .map(Bound::At)
+            .ok_or_else(|| Error::InvalidTime(s.to_owned()))
+    }
+
+    /// Resolves a time of day on the given local date.
+    pub fn on(self, date: NaiveDate) -> Option<DateTime<Utc>> {
+        match self {
+            Bound::At(t) => Some(t),
+            Bound::Clock(t) => Local
+                .from_local_datetime(&date.and_time(t))
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+        }
+    }
+}
+
+/// The time range of a filter, written as `since:10:42 until:10:45` or
+/// `last:5m`.
+///
+/// Times of day refer to the local date the filter is entered on. A range
+/// whose end is before its start wraps past midnight. `last` is measured
+/// from the time a line is checked, so it only drops earlier lines once
+/// the view is rebuilt.
+#[derive(Default)]
+pub struct TimeRange {
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    last: Option<Duration>,
+}
+
+impl TimeRange {
+    /// Takes the time terms out of a filter and returns the range and the
+    /// rest of the filter. Times of day refer to the date of `now`, the
+    /// local time of the newest line.
+    pub fn extract(s: &str, now: NaiveDateTime) -> Result<(Self, String), Error> {
+        static TERM: OnceLock<Regex> = OnceLock::new();
+
+        let term = TERM.get_or_init(|| Regex::new(r"(?:^|\s)(since|until|last):(\S+)").unwrap());
+        let (mut since, mut until, mut last) = (None, None, None);
+        for c in term.captures_iter(s) {
+            match &c[1] {
+                "since" => since = Some(Bound::parse(&c[2], false)?),
+                "until" => until = Some(Bound::parse(&c[2], true)?),
+                _ => last = Some(TimeRange::duration(&c[2])?),
+            }
+        }
+        let range = TimeRange::resolve(since, until, last, now)
+            .ok_or_else(|| Error::InvalidTime(s.to_owned()))?;
+        Ok((range, term.replace_all(s, "").trim().to_owned()))
+    }
+
+    /// Resolves the bounds on the date of `now`. If the end is a time of
+    /// day before the start, the range spans midnight: it started
+    /// yesterday if `now` is before its end, or ends tomorrow otherwise.
+    fn resolve(
+        since: Option<Bound>,
+        until: Option<Bound>,
+        last: Option<Duration>,
+        now: NaiveDateTime,
+    ) -> Option<TimeRange> {
+        let today = now.date();
+        let (mut since_date, mut until_date) = (today, today);
+        if let (Some(Bound::Clock(start)), Some(Bound::Clock(end))) = (since, until) {
+            if end < start {
+                match now.time() <= end {
+                    true => since_date = today.pred_opt()?,
+                    false => until_date = today.succ_opt()?,
+                }
+            }
+        }
+        let since = match since {
+            Some(b) => Some(b.on(since_date)?),
+            None => None,
+        };
+        let until = match until {
+            Some(b) => Some(b.on(until_date)?),
+            None => None,
+        };
+        Some(TimeRange { since, until, last })
+    }
+
+    /// Parses a duration such as `90s`, `5m` or `1h`.
+    fn duration(s: &str) -> Result<Duration, Error> {
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let n: i64 = s[..split]
+            .parse()
+            .map_err(|_| Error::InvalidTime(s.to_owned()))?;
+        match &s[split..] {
+            "ms" => Ok(Duration::milliseconds(n)),
+            "s" => Ok(Duration::seconds(n)),
+            "m" => Ok(Duration::minutes(n)),
+            "h" => Ok(Duration::hours(n)),
+            "d" => Ok(Duration::days(n)),
+            _ => Err(Error::InvalidTime(s.to_owned())),
+        }
+    }
+
+    /// Lines without a time are outside of any range.
+    pub fn contains(&self, time: Option<DateTime<Utc>>) -> bool {
+        let since = match self.last {
+            Some(d) => Some(Utc::now() - d),
+            None => self.since,
+        };
+        if since.is_none() && self.until.is_none() {
+            return true;
+        }
+        time.is_some_and(|t| since.is_none_or(|s| s <= t) && self.until.is_none_or(|u| t <= u))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimeMode {
+    Original,
+    Local,
+    Utc,
+    Relative,
+    Delta,
+}
+
+impl TimeMode {
+    pub fn next(self) -> Self {
+        match self {
+            TimeMode::Original => TimeMode::Local,
+            TimeMode::Local => TimeMode::Utc,
+            TimeMode::Utc => TimeMode::Relative,
+            TimeMode::Relative => TimeMode::Delta,
+            TimeMode::Delta => TimeMode::Original,
+        }
+    }
+
+    pub fn indicator(&self) -> &'static str {
+        match self {
+            TimeMode::Original => "",
+            TimeMode::Local => " [local]",
+            TimeMode::Utc => " [utc]",
+            TimeMode::Relative => " [relative]",
+            TimeMode::Delta => " [delta]",
+        }
+    }
+}
+
+/// Formats timestamps for display. Relative times are measured from the
+/// reference, which defaults to the first timestamp shown, and deltas
+/// from the previous timestamp shown.
+pub struct Clock {
+    mode: TimeMode,
+    reference: Option<DateTime<Utc>>,
+    fixed: bool,
+    previous: Option<DateTime<Utc>>,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self {
+            mode: TimeMode::Original,
+            reference: None,
+            fixed: false,
+            previous: None,
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: TimeMode) {
+        self.mode = mode;
+    }
+
+    pub fn set_reference(&mut self, time: DateTime<Utc>) {
+        self.reference = Some(time);
+        self.fixed = true;
+    }
+
+    /// Forgets the previous timestamp before the lines are shown again.
+    pub fn reset(&mut self) {
+        self.previous = None;
+        if !self.fixed {
+            self.reference = None;
+        }
+    }
+
+    /// Returns the display text of a timestamp, or `None` if the original
+    /// text is shown.
+    pub fn format(&mut self, time: DateTime<Utc>) -> Option<String> {
+        let previous = self.previous.replace(time).unwrap_or(time);
+        let reference = *self.reference.get_or_insert(time);
+        match self.mode {
+            TimeMode::Original => None,
+            TimeMode::Local => Some(
+                time.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S%.3f")
+                    .to_string(),
+            ),
+            TimeMode::Utc => Some(time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()),
+            TimeMode::Relative => Some(Clock::duration(time - reference)),
+            TimeMode::Delta => Some(Clock::duration(time - previous)),
+        }
+    }
+
+    fn duration(d: Duration) -> String {
+        let ms = d.num_milliseconds();
+        let sign = if ms < 0 { '-' } else { '+' };
+        let ms = ms.unsigned_abs();
+        let (hours, minutes, seconds) = (ms / 3_600_000, ms / 60_000 % 60, ms % 60_000);
+        if hours > 0 {
+            format!("{}{}h{:02}m{:02}s", sign, hours, minutes, seconds / 1000)
+        } else if minutes > 0 {
+            format!("{}{}m{:06.3}s", sign, minutes, seconds as f64 / 1000.0)
+        } else {
+            format!("{}{:.3}s", sign, seconds as f64 / 1000.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn detects_iso_timestamps() {
+        let (time, range) = Timestamp::detect("2024-01-02T10:00:00.123Z started").unwrap();
+        assert_eq!(time, utc("2024-01-02T10:00:00.123Z"));
+        assert_eq!(range, 0..24);
+        let (time, range) = Timestamp::detect("[2024-01-02 10:00:00+02:00] started").unwrap();
+        assert_eq!(time, utc("2024-01-02T08:00:00Z"));
+        assert_eq!(range, 1..26);
+    }
+
+    #[test]
+    fn detects_epoch_timestamps() {
+        let (time, range) = Timestamp::detect("1700000000 started").unwrap();
+        assert_eq!(time, utc("2023-11-14T22:13:20Z"));
+        assert_eq!(range, 0..10);
+        let (time, _) = Timestamp::detect("1700000000123 started").unwrap();
+        assert_eq!(time, utc("2023-11-14T22:13:20.123Z"));
+    }
+
+    #[test]
+    fn ignores_implausible_epoch_numbers() {
+        assert!(Timestamp::detect("9876543210 order placed").is_none());
+        assert!(Timestamp::detect("0123456789 order placed").is_none());
+        assert!(Timestamp::detect("12345678901234 order placed").is_none());
+    }
+
+    #[test]
+    fn parses_fields() {
+        assert_eq!(
+            Timestamp::parse("10/Oct/2000:13:55:36 -0700"),
+            Some(utc("2000-10-10T20:55:36Z"))
+        );
+        assert_eq!(
+            Timestamp::parse("2024-01-02T10:00:00Z"),
+            Some(utc("2024-01-02T10:00:00Z"))
+        );
+        assert_eq!(Timestamp::parse("2024-01-02T10:00:00Z trailing"), None);
+    }
+
+    fn clock(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, "%H:%M:%S%.f").unwrap()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn now(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn on(time: &str, day: &str) -> Option<DateTime<Utc>> {
+        Bound::Clock(clock(time)).on(date(day))
+    }
+
+    #[test]
+    fn parses_bounds() {
+        let end = Bound::parse("10:42", true).unwrap().on(date("2024-01-02"));
+        assert_eq!(end, on("10:42:59.999", "2024-01-02"));
+        let start = Bound::parse("10:42:30.5", false)
+            .unwrap()
+            .on(date("2024-01-02"));
+        assert_eq!(start, on("10:42:30.5", "2024-01-02"));
+        let at = Bound::parse("2024-01-02T10:00:00Z", false)
+            .unwrap()
+            .on(date("2030-01-01"));
+        assert_eq!(at, Some(utc("2024-01-02T10:00:00Z")));
+        assert!(Bound::parse("yesterday", false).is_err());
+    }
+
+    #[test]
+    fn resolves_times_of_day_on_the_current_date() {
+        let (since, until) = (
+            Bound::parse("10:00", false).ok(),
+            Bound::parse("11:00", true).ok(),
+        );
+        let range = TimeRange::resolve(since, until, None, now("2024-01-02 15:00")).unwrap();
+        assert_eq!(range.since, on("10:00:00", "2024-01-02"));
+        assert!(range.contains(on("10:30:00", "2024-01-02")));
+        assert!(!range.contains(on("10:30:00", "2024-01-01")));
+        assert!(!range.contains(None));
+    }
+
+    #[test]
+    fn wraps_ranges_past_midnight() {
+        let (since, until) = (
+            Bound::parse("23:00", false).ok(),
+            Bound::parse("01:00", true).ok(),
+        );
+        let range = TimeRange::resolve(since, until, None, now("2024-01-02 00:30")).unwrap();
+        assert_eq!(range.since, on("23:00:00", "2024-01-01"));
+        assert_eq!(range.until, on("01:00:59.999", "2024-01-02"));
+        assert!(range.contains(on("00:15:00", "2024-01-02")));
+        let range = TimeRange::resolve(since, until, None, now("2024-01-02 23:30")).unwrap();
+        assert_eq!(range.since, on("23:00:00", "2024-01-02"));
+        assert_eq!(range.until, on("01:00:59.999", "2024-01-03"));
+    }
+
+    #[test]
+    fn extracts_times_of_day_on_the_date_of_the_log() {
+        let (range, rest) =
+            TimeRange::extract("since:10:00 error", now("2023-06-01 12:00")).unwrap();
+        assert_eq!(rest, "error");
+        assert_eq!(range.since, on("10:00:00", "2023-06-01"));
+        assert!(range.contains(on("11:00:00", "2023-06-01")));
+        assert!(!range.contains(on("09:00:00", "2023-06-01")));
+    }
+
+    #[test]
+    fn extracts_time_terms() {
+        let now = Local::now().naive_local();
+        let (range, rest) = TimeRange::extract("error last:5m timeout", now).unwrap();
+        assert_eq!(rest, "error timeout");
+        assert_eq!(range.last, Some(Duration::minutes(5)));
+        assert!(range.contains(Some(Utc::now())));
+        assert!(!range.contains(Some(Utc::now() - Duration::minutes(10))));
+        assert!(TimeRange::extract("last:5x", now).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use cursive::theme::{Effect, Style};
+use cursive::utils::span::SpannedString;
+
+use crate::fields::Fields;
+
+struct Node {
+    key: String,
+    value: String,
+    field: Option<String>,
+    children: Vec<Node>,
+    open: bool,
+}
+
+impl Node {
+    fn leaf(key: &str, value: &str, field: Option<String>) -> Self {
+        let children = match value.trim_start().chars().next() {
+            Some('{') | Some('[') => serde_json::from_str(value)
+                .map(|v| Node::children(&v, field.as_deref()))
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        Self {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            field,
+            children,
+            open: true,
+        }
+    }
+
+    /// Expands a JSON object or array. Elements of arrays can't be used
+    /// in field filters, so they don't have a field name.
+    fn children(value: &serde_json::Value, field: Option<&str>) -> Vec<Node> {
+        let text = |v: &serde_json::Value| match v {
+            serde_json::Value::String(s) => s.clone(),
+            v => v.to_string(),
+        };
+        match value {
+            serde_json::Value::Object(map) => map
+                .iter()
+                .map(|(k, v)| Node::leaf(k, &text(v), field.map(|f| format!("{}.{}", f, k))))
+                .collect(),
+            serde_json::Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| Node::leaf(&i.to_string(), &text(v), None))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Inserts a field below the branch named `prefix`.
+    fn insert(&mut self, prefix: &str, path: &[&str], value: &str) {
+        let field = match prefix {
+            "" => path.first().map(|k| k.to_string()).unwrap_or_default(),
+            _ => format!("{}.{}", prefix, path.first().unwrap_or(&"")),
+        };
+        match path {
+            [] => {}
+            [key] => self.children.push(Node::leaf(key, value, Some(field))),
+            [key, rest @ ..] => {
+                let pos = match self.children.iter().position(|c| c.key == *key) {
+                    Some(pos) => pos,
+                    None => {
+                        self.children.push(Node {
+                            key: key.to_string(),
+                            value: String::new(),
+                            field: Some(field.clone()),
+                            children: Vec::new(),
+                            open: true,
+                        });
+                        self.children.len() - 1
+                    }
+                };
+                self.children[pos].insert(&field, rest, value);
+            }
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        if self.children.is_empty() || !self.value.is_empty() {
+            serde_json::from_str(&self.value)
+                .unwrap_or_else(|_| serde_json::Value::String(self.value.clone()))
+        } else {
+            serde_json::Value::Object(
+                self.children
+                    .iter()
+                    .map(|c| (c.key.clone(), c.to_json()))
+                    .collect(),
+            )
+        }
+    }
+}
+
+/// The fields of a line as a collapsible tree. Dotted keys and JSON
+/// values are expanded into branches.
+pub struct Tree {
+    root: Node,
+}
+
+impl Tree {
+    pub fn new(fields: &Fields) -> Self {
+        let mut root = Node::leaf("", "", None);
+        for (k, v) in fields.iter() {
+            let path: Vec<&str> = k.split('.').collect();
+            root.insert("", &path, v);
+        }
+        Self { root }
+    }
+
+    /// Returns the visible rows with the path of their node.
+    pub fn rows(&self, key: Style, value: Style) -> Vec<(SpannedString<Style>, Vec<usize>)> {
+        let mut rows = Vec::new();
+        Tree::collect(&self.root, &mut Vec::new(), key, value, &mut rows);
+        rows
+    }
+
+    pub fn toggle(&mut self, path: &[usize]) {
+        if let Some(node) = self.node_mut(path) {
+            node.open = !node.open;
+        }
+    }
+
+    /// Returns the value of a node, as JSON for branches.
+    pub fn value(&self, path: &[usize]) -> Option<String> {
+        self.node(path).map(|n| match n.children.is_empty() {
+            true => n.value.clone(),
+            false => n.to_json().to_string(),
+        })
+    }
+
+    /// Returns the field filter matching the value of a leaf.
+    pub fn filter(&self, path: &[usize]) -> Option<String> {
+        let node = self.node(path).filter(|n| n.children.is_empty())?;
+        let value = if node.value.is_empty() || node.value.contains(char::is_whitespace) {
+            format!("\"{}\"", node.value)
+        } else {
+            node.value.clone()
+        };
+        node.field.as_ref().map(|f| format!(".{} == {}", f, value))
+    }
+
+    fn node(&self, path: &[usize]) -> Option<&Node> {
+        path.iter()
+            .try_fold(&self.root, |node, i| node.children.get(*i))
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        path.iter()
+            .try_fold(&mut self.root, |node, i| node.children.get_mut(*i))
+    }
+
+    fn collect(
+        node: &Node,
+        path: &mut Vec<usize>,
+        key: Style,
+        value: Style,
+        rows: &mut Vec<(SpannedString<Style>, Vec<usize>)>,
+    ) {
+        for (i, child) in node.children.iter().enumerate() {
+            path.push(i);
+            let mut row = SpannedString::<Style>::plain("  ".repeat(path.len() - 1));
+            let branch = !child.children.is_empty();
+            row.append(match (branch, child.open) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                (false, _) => "  ",
+            });
+            row.append(SpannedString::styled(child.key.clone(), key));
+            if !branch {
+                row.append(SpannedString::styled(": ", Effect::Dim));
+                row.append(SpannedString::styled(child.value.clone(), value));
+            } else if !child.open {
+                row.append(SpannedString::styled(" …", Effect::Dim));
+            }
+            rows.push((row, path.clone()));
+            if branch && child.open {
+                Tree::collect(child, path, key, value, rows);
+            }
+            path.pop();
+        }
+    }
+}
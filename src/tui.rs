@@ -1,23 +1,41 @@
-use cursive::event::Key;
-use cursive::theme::Style;
+use cursive::event::{Event as CursiveEvent, EventResult, EventTrigger, Key};
+use cursive::theme::{BaseColor, Color, ColorStyle, Effect, Palette, Style};
 use cursive::traits::{Nameable, Resizable};
 use cursive::utils::span::SpannedString;
-use cursive::view::{ScrollStrategy, Scrollable};
+use cursive::view::{ScrollStrategy, Scrollable, View};
 use cursive::{
     views::{
-        DummyView, EditView, EnableableView, HideableView, LinearLayout, NamedView, OnEventView,
-        Panel, ResizedView, ScrollView, SelectView, TextContent, TextView,
+        Dialog, DummyView, EditView, EnableableView, FocusTracker, HideableView, LinearLayout,
+        NamedView, OnEventView, Panel, ResizedView, ScrollView, SelectView, TextContent, TextView,
     },
     CbSink,
 };
 use cursive::{Cursive, CursiveExt};
 
+use chrono::{DateTime, Utc};
+use regex::Regex;
 use std::io::prelude::Write;
+use std::ops::Range;
 
+use crate::bookmark::Bookmarks;
 use crate::buffer::SourceBuffer;
+use crate::clipboard::Clipboard;
+use crate::config::Config;
+use crate::context::{Context, Line};
 use crate::error::Error;
+use crate::export::{self, Entry, Format};
+use crate::fields::{Columns, Fields};
+use crate::format::Formats;
+use crate::level::Level;
+use crate::note::Notes;
+use crate::pattern::{Filter, Flags};
+use crate::semantic::Class;
 use crate::source::Source;
+use crate::status::Status;
 use crate::string::ColoredString;
+use crate::table::Table;
+use crate::timestamp::{Bound, Clock, TimeMode, Timestamp};
+use crate::tree::Tree;
 
 type HistoryHide = HideableView<LinearLayout>;
 type HistoryScroll = ScrollView<ResizedView<NamedView<SelectView>>>;
@@ -25,6 +43,13 @@ type ContentScroll = ScrollView<ResizedView<NamedView<SelectView>>>;
 type ContentEvent = OnEventView<NamedView<ContentScroll>>;
 type ContentEnableable = EnableableView<NamedView<ContentEvent>>;
 type CommandHide = HideableView<LinearLayout>;
+type MuteHide = HideableView<ResizedView<Panel<OnEventView<ScrollView<NamedView<SelectView>>>>>>;
+type BookmarkHide =
+    HideableView<ResizedView<Panel<OnEventView<ScrollView<NamedView<SelectView<char>>>>>>>;
+type PinHide =
+    HideableView<ResizedView<Panel<OnEventView<ScrollView<NamedView<SelectView<usize>>>>>>>;
+type Rule = (Regex, Style);
+type DetailHide = HideableView<ResizedView<Panel<OnEventView<ScrollView<LinearLayout>>>>>;
 
 const GLOBAL_ONEVENT: &str = "global-onevent";
 const CONTENT_VIEW: &str = "content-view";
@@ -35,29 +60,176 @@ const HISTORY_VIEW: &str = "history-view";
 const HISTORY_SCROLL: &str = "history-scroll";
 const HISTORY_HIDE: &str = "history-hide";
 const COMMAND_VIEW: &str = "command-view";
+const PROMPT_VIEW: &str = "prompt-view";
+const SEARCH_VIEW: &str = "search-view";
+const FLAGS_VIEW: &str = "flags-view";
+const FOLLOW_VIEW: &str = "follow-view";
 const COMMAND_ONEVENT: &str = "command-onevent";
 const COMMAND_HIDE: &str = "command-hide";
 const ERROR_VIEW: &str = "error-view";
 const ERROR_HIDE: &str = "error-hide";
+const MUTE_VIEW: &str = "mute-view";
+const MUTE_HIDE: &str = "mute-hide";
+const BOOKMARK_VIEW: &str = "bookmark-view";
+const BOOKMARK_HIDE: &str = "bookmark-hide";
+const NOTE_VIEW: &str = "note-view";
+const PIN_VIEW: &str = "pin-view";
+const PIN_HIDE: &str = "pin-hide";
+const DETAIL_VIEW: &str = "detail-view";
+const DETAIL_TEXT: &str = "detail-text";
+const DETAIL_HIDE: &str = "detail-hide";
+const SEMANTIC_VIEW: &str = "semantic-view";
+const TABLE_VIEW: &str = "table-view";
+const TABLE_HEADER: &str = "table-header";
 const HISTORY_LEN: usize = 50;
+const VIEW_LEN: usize = 2 * 1024;
+const NOTE_CONTEXT: usize = 3;
+const PREVIEW_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(150);
 
 enum Event {
     Clear,
     Update(String),
+    Refine(String),
+    Preview(String),
+    Highlight(String),
+    Unhighlight,
+    Matching(Matching),
+    Flags(Flags),
+    Mute(Vec<String>),
+    Level(Level),
+    Time(TimeMode),
+    Reference(DateTime<Utc>),
+    Rules(Vec<Rule>),
+    Semantic(Vec<Class>),
+    Pause(bool),
+    Bookmarks(Bookmarks),
+    Notes(Notes),
+    ExportNotes(String),
+    Export(String, Palette),
 }
 
+#[derive(Clone, Copy)]
+struct Meta {
+    no: usize,
+    level: Option<Level>,
+    time: Option<DateTime<Utc>>,
+}
+
+/// A line parsed once for filtering and rendering.
+struct Parsed {
+    meta: Meta,
+    fields: Option<Fields>,
+    /// Unstructured message of a container line, with escape sequences.
+    message: Option<String>,
+}
+
+/// A line of the content view. Separators have no line number.
+struct Row {
+    no: Option<usize>,
+    text: String,
+    /// The label starts with the bookmark and note gutter.
+    gutter: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Matching {
+    Filter,
+    Highlight,
+}
+
+impl Matching {
+    fn prompt(&self) -> &'static str {
+        match self {
+            Matching::Filter => "filter: ",
+            Matching::Highlight => "highlight: ",
+        }
+    }
+}
+
+struct Search {
+    regex: Regex,
+    backward: bool,
+}
+
+struct State {
+    mode: Mode,
+    follow: bool,
+    paused: bool,
+    anchor: Option<usize>,
+    bookmarks: Bookmarks,
+    notes: Notes,
+    pins: Vec<(usize, String)>,
+    matching: Matching,
+    searching: Option<bool>,
+    search: Option<Search>,
+    incremental: bool,
+    flags: Flags,
+    mutes: Vec<(String, bool)>,
+    level: Level,
+    time: TimeMode,
+    table: Option<Table>,
+    detail: Option<Tree>,
+    config: Option<String>,
+    semantic: Vec<Class>,
+    copy_command: Option<String>,
+    visual: Option<usize>,
+    /// Number of lines in the visual range.
+    selected: usize,
+}
+
+impl State {
+    fn indicators(&self) -> String {
+        let mut flags = self.mode.indicator().to_owned();
+        flags.push_str(&self.flags.indicators());
+        if self.level > Level::Trace {
+            flags.push_str(&format!(" [>={}]", self.level.name()));
+        }
+        flags.push_str(self.time.indicator());
+        if !self.semantic.is_empty() {
+            flags.push_str(" [semantic]");
+        }
+        if self.incremental {
+            flags.push_str(" [live]");
+        }
+        if self.visual.is_some() {
+            flags.push_str(&format!(" [visual: {} lines]", self.selected));
+        }
+        flags
+    }
+}
+
+#[derive(Clone, Copy)]
 pub enum Mode {
     RetainColors,
     RemoveColors,
     SkipColorCheck,
 }
 
+impl Mode {
+    fn indicator(&self) -> &'static str {
+        match self {
+            Mode::RetainColors => " [colors]",
+            Mode::RemoveColors => " [no-colors]",
+            Mode::SkipColorCheck => " [raw]",
+        }
+    }
+}
+
 pub struct Tui {
     siv: Cursive,
     cb_sink: CbSink,
     error: TextContent,
+    status: TextContent,
     color_mode: Mode,
     history: Option<String>,
+    context: (usize, usize),
+    incremental: bool,
+    fields: Vec<String>,
+    formats: Formats,
+    config: Option<String>,
+    rules: Vec<Rule>,
+    semantic: Vec<Class>,
+    copy_command: Option<String>,
 }
 
 impl Tui {
@@ -68,8 +240,17 @@ impl Tui {
             siv,
             cb_sink,
             error: TextContent::new(""),
+            status: TextContent::new(""),
             color_mode: Mode::SkipColorCheck,
             history: None,
+            context: (0, 0),
+            incremental: false,
+            fields: vec!["ts".to_owned(), "level".to_owned(), "msg".to_owned()],
+            formats: Formats::default(),
+            config: None,
+            rules: Vec::new(),
+            semantic: Vec::new(),
+            copy_command: None,
         }
     }
 
@@ -82,20 +263,80 @@ impl Tui {
         self.history = Some(path);
     }
 
-    fn init_events(&mut self) {
+    pub fn set_context(&mut self, before: usize, after: usize) {
+        self.context = (before, after);
+    }
+
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
+
+    pub fn set_fields(&mut self, fields: Vec<String>) {
+        self.fields = fields;
+    }
+
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.formats = formats;
+    }
+
+    /// Enables all semantic highlighters on start.
+    pub fn set_semantic(&mut self, enabled: bool) {
+        self.semantic = match enabled {
+            true => Class::ALL.to_vec(),
+            false => Vec::new(),
+        };
+    }
+
+    /// Pipes yanked text to a command instead of copying it with OSC 52.
+    pub fn set_copy_command(&mut self, command: Option<String>) {
+        self.copy_command = command;
+    }
+
+    /// Sets the configuration file and the highlight rules loaded from it.
+    pub fn set_config(&mut self, path: Option<String>, rules: Vec<Rule>) {
+        self.config = path;
+        self.rules = rules;
+    }
+
+    fn init_events(&mut self, tx: std::sync::mpsc::Sender<Event>) {
         if let Some(mut v) = self.siv.find_name::<ContentEvent>(CONTENT_EVENT) {
-            v.set_on_pre_event('G', |siv| {
-                if let Some(mut v) = siv.find_name::<SelectView<String>>(CONTENT_VIEW) {
-                    let len = v.len();
-                    v.set_selection(len - 1)(siv);
+            v.set_on_pre_event('G', |siv| Tui::set_follow(siv, true));
+            v.set_on_pre_event('F', |siv| {
+                let follow = siv.user_data::<State>().is_some_and(|s| s.follow);
+                Tui::set_follow(siv, !follow);
+            });
+            let pause_tx = tx.clone();
+            v.set_on_pre_event('p', move |siv| Tui::on_toggle_pause(siv, &pause_tx));
+            let mark_tx = tx.clone();
+            v.set_on_pre_event('m', move |siv| {
+                let tx = mark_tx.clone();
+                Tui::prompt_letter(siv, "Mark", move |s, name| Tui::on_set_mark(s, name, &tx))
+            });
+            v.set_on_pre_event('\'', |siv| {
+                Tui::prompt_letter(siv, "Jump to mark", Tui::on_jump_mark)
+            });
+            v.set_on_pre_event('b', |siv| Tui::on_next_mark(siv, false));
+            v.set_on_pre_event('B', |siv| Tui::on_next_mark(siv, true));
+            v.set_on_pre_event('M', Tui::on_toggle_bookmarks);
+            let note_tx = tx.clone();
+            v.set_on_pre_event('a', move |siv| Tui::on_open_note(siv, &note_tx));
+            v.set_on_pre_event('P', Tui::on_toggle_pin);
+            v.set_on_pre_event('V', Tui::on_toggle_visual);
+            v.set_on_pre_event('y', Tui::on_yank);
+            v.set_on_pre_event('w', |siv| {
+                if let Some(mut v) = siv.find_name::<EditView>(COMMAND_VIEW) {
+                    v.set_content(":w ");
                 }
-                if let Some(mut v) = siv.find_name::<ContentScroll>(CONTENT_SCROLL) {
-                    v.scroll_to_bottom();
-                    v.set_scroll_strategy(ScrollStrategy::StickToBottom);
+                siv.focus_name(COMMAND_VIEW).unwrap();
+            });
+            v.set_on_pre_event(CursiveEvent::CtrlChar('p'), |siv| {
+                if siv.user_data::<State>().is_some_and(|s| !s.pins.is_empty()) {
+                    siv.focus_name(PIN_VIEW).unwrap();
                 }
             });
             v.set_on_pre_event('g', |siv| {
-                if let Some(mut v) = siv.find_name::<SelectView<String>>(CONTENT_VIEW) {
+                Tui::set_follow(siv, false);
+                if let Some(mut v) = siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
                     v.set_selection(0)(siv);
                 }
                 if let Some(mut v) = siv.find_name::<ContentScroll>(CONTENT_SCROLL) {
@@ -113,34 +354,203 @@ impl Tui {
                     v.scroll_to_right();
                 }
             });
+            v.set_on_pre_event('/', |siv| Tui::on_start_search(siv, false));
+            v.set_on_pre_event('?', |siv| Tui::on_start_search(siv, true));
+            v.set_on_pre_event('n', |siv| Tui::on_search_next(siv, false));
+            v.set_on_pre_event('N', |siv| Tui::on_search_next(siv, true));
+            for (key, level) in ('1'..).zip(Level::ALL.iter()) {
+                let tx = tx.clone();
+                v.set_on_pre_event(key, move |siv| Tui::on_min_level(siv, *level, &tx));
+            }
+            let time_tx = tx.clone();
+            v.set_on_pre_event('t', move |siv| Tui::on_cycle_time(siv, &time_tx));
+            let reference_tx = tx.clone();
+            let formats = self.formats.clone();
+            v.set_on_pre_event('T', move |siv| {
+                Tui::on_set_reference(siv, &formats, &reference_tx)
+            });
+            let formats = self.formats.clone();
+            let fields = self.fields.clone();
+            v.set_on_pre_event('v', move |siv| Tui::on_open_table(siv, &formats, &fields));
+            v.set_on_pre_event('h', move |siv| Tui::on_open_semantic(siv, &tx));
         }
     }
 
     fn build_ui(&mut self, tx: std::sync::mpsc::Sender<Event>) {
         let path = self.history.take();
+        let (history, mutes) = path.as_deref().map(Tui::load).unwrap_or_default();
+        let toggle_tx = tx.clone();
+        let init_tx = tx.clone();
+        let mute_tx = tx.clone();
+        let unmute_tx = tx.clone();
+        let delete_tx = tx.clone();
+        let unmark_tx = tx.clone();
+        let edit_tx = tx.clone();
+        let formats = self.formats.clone();
+        let detail_formats = self.formats.clone();
+        let detail_tx = tx.clone();
+        let case_tx = tx.clone();
+        let literal_tx = tx.clone();
+        let word_tx = tx.clone();
+        let fuzzy_tx = tx.clone();
         self.siv.add_fullscreen_layer(
             OnEventView::new(ResizedView::with_full_screen(
                 LinearLayout::vertical()
                     .child(ResizedView::with_full_height(
-                        Panel::new(
-                            EnableableView::new(
-                                OnEventView::new(
-                                    SelectView::<String>::new()
-                                        .with_name(CONTENT_VIEW)
-                                        .full_width()
-                                        .scrollable()
-                                        .show_scrollbars(false)
-                                        .scroll_y(true)
-                                        .scroll_x(true)
-                                        .scroll_strategy(ScrollStrategy::StickToBottom)
-                                        .with_name(CONTENT_SCROLL),
+                        LinearLayout::horizontal()
+                            .child(
+                                LinearLayout::vertical()
+                                    .child(
+                                        HideableView::new(
+                                            Panel::new(
+                                                OnEventView::new(
+                                                    SelectView::<usize>::new()
+                                                        .on_submit(|s, no: &usize| {
+                                                            Tui::on_jump_pin(s, *no)
+                                                        })
+                                                        .with_name(PIN_VIEW)
+                                                        .scrollable(),
+                                                )
+                                                .on_pre_event('d', Tui::on_delete_pin)
+                                                .on_pre_event('K', |s| Tui::on_move_pin(s, true))
+                                                .on_pre_event('J', |s| Tui::on_move_pin(s, false))
+                                                .on_pre_event('q', Tui::on_leave_panel)
+                                                .on_pre_event(Key::Esc, Tui::on_leave_panel),
+                                            )
+                                            .title("Pinned")
+                                            .max_height(8),
+                                        )
+                                        .hidden()
+                                        .with_name(PIN_HIDE),
+                                    )
+                                    .child(
+                                        Panel::new(
+                                            EnableableView::new(
+                                                OnEventView::new(
+                                                    SelectView::<Row>::new()
+                                                        .on_submit(move |s, row: &Row| {
+                                                            Tui::on_open_detail(
+                                                                s,
+                                                                row,
+                                                                &detail_formats,
+                                                            )
+                                                        })
+                                                        .on_select(|s, _| Tui::refresh_visual(s))
+                                                        .with_name(CONTENT_VIEW)
+                                                        .full_width()
+                                                        .scrollable()
+                                                        .show_scrollbars(false)
+                                                        .scroll_y(true)
+                                                        .scroll_x(true)
+                                                        .scroll_strategy(
+                                                            ScrollStrategy::StickToBottom,
+                                                        )
+                                                        .on_scroll_inner(|v, _| {
+                                                            let bottom = v.is_at_bottom();
+                                                            EventResult::with_cb(move |s| {
+                                                                Tui::on_scroll_content(s, bottom)
+                                                            })
+                                                        })
+                                                        .with_name(CONTENT_SCROLL),
+                                                )
+                                                .with_name(CONTENT_EVENT),
+                                            )
+                                            .with_name(CONTENT_ENABLE),
+                                        )
+                                        .title("Log View")
+                                        .full_height(),
+                                    )
+                                    .child(
+                                        HideableView::new(
+                                            Panel::new(
+                                                OnEventView::new(
+                                                    LinearLayout::vertical()
+                                                        .child(
+                                                            TextView::new("")
+                                                                .with_name(DETAIL_TEXT),
+                                                        )
+                                                        .child(
+                                                            SelectView::<Vec<usize>>::new()
+                                                                .on_submit(
+                                                                    |s, path: &Vec<usize>| {
+                                                                        Tui::on_toggle_detail(
+                                                                            s, path,
+                                                                        )
+                                                                    },
+                                                                )
+                                                                .with_name(DETAIL_VIEW),
+                                                        )
+                                                        .scrollable(),
+                                                )
+                                                .on_pre_event('c', Tui::on_copy_detail)
+                                                .on_pre_event('f', move |s| {
+                                                    Tui::on_filter_detail(s, &detail_tx)
+                                                })
+                                                .on_pre_event('q', Tui::on_close_detail)
+                                                .on_pre_event(Key::Esc, Tui::on_close_detail),
+                                            )
+                                            .title("Detail")
+                                            .max_height(16),
+                                        )
+                                        .hidden()
+                                        .with_name(DETAIL_HIDE),
+                                    )
+                                    .full_width(),
+                            )
+                            .child(
+                                HideableView::new(
+                                    Panel::new(
+                                        OnEventView::new(
+                                            SelectView::<String>::new()
+                                                .on_submit(move |s, pattern: &String| {
+                                                    Tui::on_toggle_mute(s, pattern, &unmute_tx)
+                                                })
+                                                .with_name(MUTE_VIEW)
+                                                .scrollable(),
+                                        )
+                                        .on_pre_event('d', move |s| {
+                                            Tui::on_delete_mute(s, &delete_tx)
+                                        })
+                                        .on_pre_event('q', Tui::on_leave_panel)
+                                        .on_pre_event(Key::Esc, Tui::on_leave_panel),
+                                    )
+                                    .title("Muted")
+                                    .max_width(40),
                                 )
-                                .with_name(CONTENT_EVENT),
+                                .with_name(MUTE_HIDE),
                             )
-                            .with_name(CONTENT_ENABLE),
-                        )
-                        .title("Log View"),
+                            .child(
+                                HideableView::new(
+                                    Panel::new(
+                                        OnEventView::new(
+                                            SelectView::<char>::new()
+                                                .on_submit(|s, name: &char| {
+                                                    Tui::on_jump_mark(s, *name)
+                                                })
+                                                .with_name(BOOKMARK_VIEW)
+                                                .scrollable(),
+                                        )
+                                        .on_pre_event('d', move |s| {
+                                            Tui::on_delete_mark(s, &unmark_tx)
+                                        })
+                                        .on_pre_event('q', Tui::on_close_bookmarks)
+                                        .on_pre_event(Key::Esc, Tui::on_close_bookmarks),
+                                    )
+                                    .title("Bookmarks")
+                                    .max_width(40),
+                                )
+                                .hidden()
+                                .with_name(BOOKMARK_HIDE),
+                            ),
                     ))
+                    .child(
+                        LinearLayout::horizontal()
+                            .child(DummyView)
+                            .child(TextView::new_with_content(self.status.clone()).full_width())
+                            .child(TextView::new("").with_name(FOLLOW_VIEW))
+                            .child(TextView::new("").with_name(FLAGS_VIEW))
+                            .child(DummyView),
+                    )
                     .child(
                         HideableView::new(
                             LinearLayout::horizontal()
@@ -164,18 +574,51 @@ impl Tui {
                             LinearLayout::horizontal()
                                 .child(DummyView)
                                 .child(
-                                    OnEventView::new(
-                                        EditView::new()
-                                            .on_submit(move |s, cmd| {
-                                                Tui::on_submit_command(s, cmd, &tx)
+                                    TextView::new(Matching::Filter.prompt()).with_name(PROMPT_VIEW),
+                                )
+                                .child(
+                                    FocusTracker::new(
+                                        OnEventView::new(
+                                            EditView::new()
+                                                .on_submit(move |s, cmd| {
+                                                    Tui::on_submit_command(s, cmd, &formats, &tx)
+                                                })
+                                                .on_edit(move |s, cmd, _| {
+                                                    Tui::on_edit_command(s, cmd, &edit_tx)
+                                                })
+                                                .with_name(COMMAND_VIEW)
+                                                .fixed_height(1)
+                                                .full_width(),
+                                        )
+                                        .on_pre_event(Key::Tab, Tui::on_show_history)
+                                        .on_pre_event(
+                                            CursiveEvent::CtrlChar('l'),
+                                            Tui::on_toggle_incremental,
+                                        )
+                                        .on_pre_event(CursiveEvent::CtrlChar('s'), move |s| {
+                                            Tui::on_toggle_flag(s, &case_tx, Flags::next_case)
+                                        })
+                                        .on_pre_event(CursiveEvent::CtrlChar('r'), move |s| {
+                                            Tui::on_toggle_flag(s, &literal_tx, |f| {
+                                                f.literal = !f.literal
                                             })
-                                            .with_name(COMMAND_VIEW)
-                                            .fixed_height(1)
-                                            .full_width(),
+                                        })
+                                        .on_pre_event(CursiveEvent::CtrlChar('w'), move |s| {
+                                            Tui::on_toggle_flag(s, &word_tx, |f| f.word = !f.word)
+                                        })
+                                        .on_pre_event(CursiveEvent::CtrlChar('f'), move |s| {
+                                            Tui::on_toggle_flag(s, &fuzzy_tx, |f| {
+                                                f.fuzzy = !f.fuzzy
+                                            })
+                                        })
+                                        .on_pre_event(CursiveEvent::CtrlChar('x'), move |s| {
+                                            Tui::on_add_mute(s, &mute_tx)
+                                        })
+                                        .with_name(COMMAND_ONEVENT),
                                     )
-                                    .on_pre_event(Key::Tab, Tui::on_show_history)
-                                    .with_name(COMMAND_ONEVENT),
+                                    .on_focus_lost(|_| EventResult::with_cb(Tui::on_cancel_search)),
                                 )
+                                .child(TextView::new("").with_name(SEARCH_VIEW))
                                 .child(DummyView),
                         )
                         .with_name(COMMAND_HIDE),
@@ -194,15 +637,52 @@ impl Tui {
                         .with_name(ERROR_HIDE),
                     ),
             ))
-            .on_pre_event(Key::Esc, move |siv| Tui::quit(siv, &path))
+            // after the focused view, so panes can handle Esc themselves
+            .on_event(Key::Esc, move |siv| Tui::on_escape(siv, &path))
+            .on_pre_event(CursiveEvent::CtrlChar('t'), move |siv| {
+                Tui::on_toggle_matching(siv, &toggle_tx)
+            })
             .with_name(GLOBAL_ONEVENT),
         );
 
         if let Some(mut v) = self.siv.find_name::<HistoryHide>(HISTORY_HIDE) {
             v.hide();
         }
+        if let Some(mut v) = self.siv.find_name::<SelectView>(HISTORY_VIEW) {
+            v.add_all_str(history);
+        }
 
-        self.init_events();
+        let state = State {
+            mode: self.color_mode,
+            follow: true,
+            paused: false,
+            anchor: None,
+            bookmarks: Bookmarks::default(),
+            notes: Notes::default(),
+            pins: Vec::new(),
+            matching: Matching::Filter,
+            searching: None,
+            search: None,
+            incremental: self.incremental,
+            flags: Flags::default(),
+            mutes,
+            level: Level::Trace,
+            time: TimeMode::Original,
+            table: None,
+            detail: None,
+            config: self.config.clone(),
+            semantic: self.semantic.clone(),
+            copy_command: self.copy_command.clone(),
+            visual: None,
+            selected: 0,
+        };
+        if let Some(mut v) = self.siv.find_name::<TextView>(FLAGS_VIEW) {
+            v.set_content(state.indicators());
+        }
+        self.siv.set_user_data(state);
+        Tui::refresh_follow(&mut self.siv);
+        Tui::refresh_mutes(&mut self.siv, &init_tx);
+        self.init_events(init_tx.clone());
         self.siv.focus_name(COMMAND_VIEW).unwrap();
         self.siv.set_fps(30);
     }
@@ -210,25 +690,64 @@ impl Tui {
     pub fn run(mut self, source: Source<String>) {
         let (tx, rx) = std::sync::mpsc::channel();
         self.build_ui(tx);
+        let styles = (self.match_styles(), self.level_styles());
         match self.color_mode {
-            Mode::SkipColorCheck => self.spawn_update(source, rx, ColoredString::unstyled),
-            Mode::RemoveColors => self.spawn_update(source, rx, ColoredString::plain),
-            Mode::RetainColors => self.spawn_update(source, rx, ColoredString::styled),
+            Mode::SkipColorCheck => self.spawn_update(source, rx, ColoredString::unstyled, styles),
+            Mode::RemoveColors => self.spawn_update(source, rx, ColoredString::plain, styles),
+            Mode::RetainColors => self.spawn_update(source, rx, ColoredString::styled, styles),
         };
 
         self.siv.run();
     }
 
+    fn match_styles(&self) -> Vec<Style> {
+        let palette = &self.siv.current_theme().palette;
+        let mut colors: Vec<Color> = (1..)
+            .map(|i| palette.custom(&format!("match_{}", i)).copied())
+            .take_while(Option::is_some)
+            .flatten()
+            .collect();
+        if colors.is_empty() {
+            colors = vec![
+                Color::Dark(BaseColor::Yellow),
+                Color::Dark(BaseColor::Cyan),
+                Color::Dark(BaseColor::Magenta),
+                Color::Dark(BaseColor::Green),
+            ];
+        }
+        colors
+            .into_iter()
+            .map(|c| ColorStyle::new(Color::Dark(BaseColor::Black), c).into())
+            .collect()
+    }
+
+    fn level_styles(&self) -> Vec<Style> {
+        Level::ALL
+            .iter()
+            .map(|l| Tui::custom_style(&self.siv, l.key()))
+            .collect()
+    }
+
+    fn custom_style(siv: &Cursive, key: &str) -> Style {
+        siv.current_theme()
+            .palette
+            .custom(key)
+            .map_or(Style::none(), |c| ColorStyle::front(*c).into())
+    }
+
     fn select_view_append<T: 'static>(
         siv: &mut Cursive,
         id: &str,
         label: SpannedString<Style>,
         value: T,
     ) {
+        let follow = siv.user_data::<State>().is_none_or(|s| s.follow);
         if let Some(mut v) = siv.find_name::<SelectView<T>>(id) {
             let len = v.len();
             v.add_item(label, value);
-            v.set_selection(len)(siv);
+            if follow {
+                v.set_selection(len)(siv);
+            }
         } else {
             siv.quit();
         }
@@ -247,120 +766,369 @@ impl Tui {
         source: Source<String>,
         rx: std::sync::mpsc::Receiver<Event>,
         parser: impl Fn(&str) -> SpannedString<Style> + Send + Copy + 'static,
+        (styles, levels): (Vec<Style>, Vec<Style>),
     ) {
         let cb_sink = self.cb_sink.clone();
         let error = self.error.clone();
+        let status = self.status.clone();
+        let mut context = Context::new(self.context.0, self.context.1);
+        let mut columns = Columns::new(
+            self.fields.clone(),
+            Tui::custom_style(&self.siv, "field_key"),
+            Tui::custom_style(&self.siv, "field_value"),
+        );
+        let formats = self.formats.clone();
+        let mut clock = Clock::new();
+        let mut rules = std::mem::take(&mut self.rules);
+        let classes: Vec<(Class, Rule)> = Class::ALL
+            .iter()
+            .map(|c| (*c, (c.regex(), Tui::custom_style(&self.siv, c.key()))))
+            .collect();
+        let enable = move |enabled: &[Class]| -> Vec<Rule> {
+            classes
+                .iter()
+                .filter(|(c, _)| enabled.contains(c))
+                .map(|(_, rule)| rule.clone())
+                .collect()
+        };
+        let mut semantic = enable(&self.semantic);
+        let gutter = Tui::custom_style(&self.siv, "bookmark");
         std::thread::spawn(move || {
             let mut lines = 0;
+            let mut stats = Status::new();
             let mut buffer: SourceBuffer<String> = SourceBuffer::new(source);
-            let mut filter: Option<regex::Regex> = None;
+            let mut flags = Flags::default();
+            let mut pattern: Option<String> = None;
+            let mut filter: Option<Filter> = None;
+            let mut marks: Vec<String> = Vec::new();
+            let mut highlights: Vec<(Regex, Style)> = Vec::new();
+            let mut muted: Vec<Regex> = Vec::new();
+            let mut previous: Option<Meta> = None;
+            let mut min_level = Level::Trace;
+            let mut matching = Matching::Filter;
+            let mut preview: Option<(String, std::time::Instant)> = None;
+            let mut next: Option<Event> = None;
+            let mut held: Option<Vec<(SpannedString<Style>, Row)>> = None;
+            let mut replay = true;
+            let mut bookmarks = Bookmarks::default();
+            let mut notes = Notes::default();
+            let append = |(label, value): (SpannedString<Style>, Row)| {
+                cb_sink
+                    .send(Box::new(move |siv| {
+                        Tui::select_view_append::<Row>(siv, CONTENT_VIEW, label, value)
+                    }))
+                    .is_ok()
+            };
+            let clear = || {
+                cb_sink
+                    .send(Box::new(|siv| {
+                        Tui::anchor_content(siv);
+                        Tui::select_view_clear::<Row>(siv, CONTENT_VIEW)
+                    }))
+                    .is_ok()
+            };
             loop {
                 if let Some(s) = buffer.update() {
-                    if let Some(ref r) = filter {
-                        if r.is_match(&s) {
-                            let s: String = s;
-                            if cb_sink
-                                .send(Box::new(move |siv| {
-                                    Tui::select_view_append::<String>(
-                                        siv,
-                                        CONTENT_VIEW,
-                                        parser(&s),
-                                        Default::default(),
-                                    )
-                                }))
-                                .is_err()
-                            {
-                                return;
-                            }
-                            lines += 1;
+                    let parsed = Tui::parse(buffer.count() - 1, &s, &formats, previous);
+                    previous = Some(parsed.meta);
+                    let is_muted = muted.iter().any(|r| r.is_match(&s));
+                    if is_muted {
+                        stats.hidden += 1;
+                    } else if parsed.meta.level.is_none_or(|l| l >= min_level) {
+                        let is_match = Tui::is_visible(&s, &parsed, &filter, matching);
+                        if is_match {
+                            stats.matched += 1;
                         }
-                    } else if filter.is_none() {
-                        if cb_sink
-                            .send(Box::new(move |siv| {
-                                Tui::select_view_append::<String>(
-                                    siv,
-                                    CONTENT_VIEW,
-                                    parser(&s),
-                                    Default::default(),
-                                )
-                            }))
-                            .is_err()
-                        {
-                            return;
+                        for line in context.process(parsed, is_match) {
+                            let line = Tui::render(
+                                line,
+                                &buffer,
+                                &parser,
+                                &mut columns,
+                                &mut clock,
+                                (&semantic, &rules, &highlights),
+                                &levels,
+                                (&bookmarks, &notes, gutter),
+                            );
+                            lines += 1;
+                            stats.shown += 1;
+                            match held.as_mut() {
+                                Some(held) => {
+                                    // lines beyond the limit are replayed on resume
+                                    if lines <= VIEW_LEN {
+                                        held.push(line);
+                                    }
+                                    stats.held = stats.held.map(|n| n + 1);
+                                }
+                                None => {
+                                    if !append(line) {
+                                        return;
+                                    }
+                                }
+                            }
                         }
-                        lines += 1;
                     }
+                    status.set_content(stats.text(&buffer));
                 }
-                if lines > (2 * 1024) {
-                    if cb_sink
-                        .send(Box::new(|siv| {
-                            Tui::select_view_clear::<String>(siv, CONTENT_VIEW)
-                        }))
-                        .is_err()
-                    {
+                if stats.tick(buffer.count()) {
+                    status.set_content(stats.text(&buffer));
+                }
+                if held.is_none() && lines > VIEW_LEN {
+                    if !clear() {
                         return;
                     }
                     lines = 0;
+                    replay = true;
                 }
-                if let Ok(ev) = rx.try_recv() {
-                    if cb_sink
-                        .send(Box::new(|siv| {
-                            Tui::select_view_clear::<String>(siv, CONTENT_VIEW)
-                        }))
-                        .is_err()
-                    {
+                let ev = match next.take().or_else(|| rx.try_recv().ok()) {
+                    Some(Event::Preview(s)) => {
+                        preview = Some((s, std::time::Instant::now()));
+                        None
+                    }
+                    Some(Event::ExportNotes(path)) => {
+                        let report = notes.report(NOTE_CONTEXT, |no| {
+                            buffer
+                                .get(no)
+                                .map(|s| ColoredString::strip(s).trim_end().to_owned())
+                        });
+                        match std::fs::write(&path, report) {
+                            Ok(_) => error.set_content(format!(
+                                "Wrote {} notes to '{}'",
+                                notes.len(),
+                                path
+                            )),
+                            Err(_) => error.set_content(format!("{:?}", Error::WriteFailed(path))),
+                        }
+                        None
+                    }
+                    Some(Event::Export(path, palette)) => {
+                        let mut meta: Option<Meta> = None;
+                        let entries: Vec<Entry> = buffer
+                            .iter()
+                            .enumerate()
+                            .map(|(i, s)| {
+                                let no = buffer.first() + i;
+                                let m = Tui::meta(no, s, &formats, meta);
+                                meta = Some(m);
+                                Entry {
+                                    no: Some(no),
+                                    text: s.clone(),
+                                    label: parser(s),
+                                    level: m.level,
+                                    time: m.time,
+                                }
+                            })
+                            .collect();
+                        let format = Format::from_path(&path);
+                        match export::write(&path, format, &entries, &formats, &palette) {
+                            Ok(_) => error.set_content(format!(
+                                "Wrote {} lines to '{}'",
+                                entries.len(),
+                                path
+                            )),
+                            Err(e) => error.set_content(format!("{:?}", e)),
+                        }
+                        None
+                    }
+                    Some(Event::Bookmarks(b)) => {
+                        bookmarks = b;
+                        None
+                    }
+                    Some(Event::Notes(n)) => {
+                        notes = n;
+                        None
+                    }
+                    Some(Event::Pause(true)) => {
+                        held = Some(Vec::new());
+                        stats.held = Some(0);
+                        status.set_content(stats.text(&buffer));
+                        None
+                    }
+                    Some(Event::Pause(false)) => {
+                        if lines <= VIEW_LEN {
+                            for line in held.take().unwrap_or_default() {
+                                if !append(line) {
+                                    return;
+                                }
+                            }
+                            // marks may have changed while the lines were held
+                            if cb_sink.send(Box::new(Tui::refresh_gutter)).is_err() {
+                                return;
+                            }
+                        }
+                        held = None;
+                        stats.held = None;
+                        status.set_content(stats.text(&buffer));
+                        None
+                    }
+                    Some(ev) => {
+                        preview = None;
+                        Some(ev)
+                    }
+                    None => match preview.take() {
+                        Some((s, t)) if t.elapsed() >= PREVIEW_DEBOUNCE => {
+                            Tui::preview(s, &flags, Tui::newest(&buffer, &formats))
+                        }
+                        p => {
+                            preview = p;
+                            None
+                        }
+                    },
+                };
+                if let Some(ev) = ev {
+                    if !clear() {
                         return;
                     }
                     lines = 0;
+                    replay = true;
+                    // a refinement adds a condition to the current filter
+                    let ev = match (ev, &pattern) {
+                        (Event::Refine(s), Some(p)) => Event::Update(format!("{} && {}", p, s)),
+                        (Event::Refine(s), None) => Event::Update(s),
+                        (ev, _) => ev,
+                    };
                     match ev {
                         Event::Clear => {
+                            pattern = None;
                             filter = None;
                         }
-                        Event::Update(s) => match regex::Regex::new(&s) {
-                            Ok(r) => filter = Some(r),
+                        Event::Update(s) => {
+                            match Filter::new(&s, &flags, Tui::newest(&buffer, &formats)) {
+                                Ok(f) => {
+                                    pattern = Some(s);
+                                    filter = Some(f);
+                                }
+                                Err(e) => {
+                                    error.set_content(format!("{:?}", e));
+                                }
+                            }
+                        }
+                        Event::Highlight(s) => match flags.compile(&s) {
+                            Ok(r) => {
+                                let style = styles[highlights.len() % styles.len()];
+                                highlights.push((r, style));
+                                marks.push(s);
+                            }
                             Err(e) => {
                                 error.set_content(format!("{:?}", e));
                             }
                         },
+                        Event::Unhighlight => {
+                            marks.clear();
+                            highlights.clear();
+                        }
+                        Event::Flags(f) => {
+                            flags = f;
+                            if let Some(s) = &pattern {
+                                match Filter::new(s, &flags, Tui::newest(&buffer, &formats)) {
+                                    Ok(f) => filter = Some(f),
+                                    Err(e) => error.set_content(format!("{:?}", e)),
+                                }
+                            }
+                            highlights = Tui::compile_all(&marks, &flags, &error)
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, r)| (r, styles[i % styles.len()]))
+                                .collect();
+                        }
+                        Event::Mute(mutes) => {
+                            // mutes are persisted, so they ignore the filter flags
+                            muted = Tui::compile_all(&mutes, &Flags::default(), &error);
+                        }
+                        Event::Matching(m) => {
+                            matching = m;
+                        }
+                        Event::Rules(r) => {
+                            rules = r;
+                        }
+                        Event::Semantic(c) => {
+                            semantic = enable(&c);
+                        }
+                        Event::Level(l) => {
+                            min_level = l;
+                        }
+                        Event::Time(m) => {
+                            clock.set_mode(m);
+                        }
+                        Event::Reference(t) => {
+                            clock.set_reference(t);
+                        }
+                        Event::Refine(_)
+                        | Event::Preview(_)
+                        | Event::Pause(_)
+                        | Event::Bookmarks(_)
+                        | Event::Notes(_)
+                        | Event::ExportNotes(_)
+                        | Event::Export(..) => {}
                     }
+                    stats.filtering = Tui::is_filtering(&filter, matching);
                 }
-                if lines == 0 {
-                    for item in buffer.iter() {
-                        let item: String = item.into();
-                        if let Some(ref r) = filter {
-                            if r.is_match(&item) {
-                                if cb_sink
-                                    .send(Box::new(move |siv| {
-                                        Tui::select_view_append::<String>(
-                                            siv,
-                                            CONTENT_VIEW,
-                                            parser(&item),
-                                            Default::default(),
-                                        )
-                                    }))
-                                    .is_err()
-                                {
-                                    return;
-                                }
-                                lines += 1;
+                if replay {
+                    replay = false;
+                    context.reset();
+                    clock.reset();
+                    stats.reset();
+                    if let Some(held) = held.as_mut() {
+                        held.clear();
+                        stats.held = Some(0);
+                    }
+                    previous = None;
+                    columns.reset();
+                    let parsed: Vec<Parsed> = (buffer.first()..)
+                        .zip(buffer.iter())
+                        .map(|(no, item)| {
+                            let parsed = Tui::parse(no, item, &formats, previous);
+                            previous = Some(parsed.meta);
+                            if let Some(fields) = &parsed.fields {
+                                columns.measure(fields);
                             }
-                        } else if filter.is_none() {
-                            if cb_sink
-                                .send(Box::new(move |siv| {
-                                    Tui::select_view_append::<String>(
-                                        siv,
-                                        CONTENT_VIEW,
-                                        parser(&item),
-                                        Default::default(),
-                                    )
-                                }))
-                                .is_err()
-                            {
+                            parsed
+                        })
+                        .collect();
+                    for (item, parsed) in buffer.iter().zip(parsed) {
+                        if let Ok(ev) = rx.try_recv() {
+                            next = Some(ev);
+                            break;
+                        }
+                        if muted.iter().any(|r| r.is_match(item)) {
+                            stats.hidden += 1;
+                            continue;
+                        }
+                        if parsed.meta.level.is_some_and(|l| l < min_level) {
+                            continue;
+                        }
+                        let is_match = Tui::is_visible(item, &parsed, &filter, matching);
+                        if is_match {
+                            stats.matched += 1;
+                        }
+                        for line in context.process(parsed, is_match) {
+                            if !append(Tui::render(
+                                line,
+                                &buffer,
+                                &parser,
+                                &mut columns,
+                                &mut clock,
+                                (&semantic, &rules, &highlights),
+                                &levels,
+                                (&bookmarks, &notes, gutter),
+                            )) {
                                 return;
                             }
                             lines += 1;
+                            stats.shown += 1;
+                        }
+                    }
+                    if next.is_some() {
+                        // replay again once the interrupting event is handled
+                        if !clear() {
+                            return;
                         }
+                        lines = 0;
+                        replay = true;
+                    } else if cb_sink.send(Box::new(Tui::restore_anchor)).is_err() {
+                        return;
                     }
+                    status.set_content(stats.text(&buffer));
                 } else {
                     std::thread::sleep(std::time::Duration::new(0, 200000));
                 }
@@ -368,6 +1136,172 @@ impl Tui {
         });
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn render(
+        line: Line<Parsed>,
+        buffer: &SourceBuffer<String>,
+        parser: &impl Fn(&str) -> SpannedString<Style>,
+        columns: &mut Columns,
+        clock: &mut Clock,
+        highlights: (&[Rule], &[Rule], &[Rule]),
+        levels: &[Style],
+        (bookmarks, notes, style): (&Bookmarks, &Notes, Style),
+    ) -> (SpannedString<Style>, Row) {
+        let text = |meta: &Meta| buffer.get(meta.no).map_or("", |s| s.as_str());
+        let mut label = |parsed: Parsed| {
+            let (meta, text) = (parsed.meta, text(&parsed.meta));
+            let mut label = match (parsed.fields, parsed.message) {
+                // keep the escape sequences of unstructured container messages
+                (Some(_), Some(msg)) => parser(&msg),
+                (Some(mut fields), None) => {
+                    if let Some((k, t)) = Timestamp::field(&fields) {
+                        if let Some(s) = clock.format(t) {
+                            fields.insert(k, s);
+                        }
+                    }
+                    columns.render(&fields)
+                }
+                (None, _) => {
+                    let label = parser(text);
+                    match Timestamp::detect(label.source()) {
+                        Some((t, range)) => match clock.format(t) {
+                            Some(s) => ColoredString::replace(label, range, &s),
+                            None => label,
+                        },
+                        None => label,
+                    }
+                }
+            };
+            if let Some(l) = meta.level {
+                label = ColoredString::tint(label, levels[l as usize]);
+            }
+            let (semantic, rules, marks) = highlights;
+            [semantic, rules, marks]
+                .iter()
+                .fold(label, |label, rules| ColoredString::highlight(label, rules))
+        };
+        let gutter = !(bookmarks.is_empty() && notes.is_empty());
+        let row = |meta: &Meta| Row {
+            no: Some(meta.no),
+            text: text(meta).to_owned(),
+            gutter,
+        };
+        let (row, label) = match line {
+            Line::Match(parsed) => (row(&parsed.meta), label(parsed)),
+            Line::Context(parsed) => (row(&parsed.meta), ColoredString::dim(label(parsed))),
+            Line::Separator => (
+                Row {
+                    no: None,
+                    text: String::new(),
+                    gutter,
+                },
+                SpannedString::styled("--", Effect::Dim),
+            ),
+        };
+        if !gutter {
+            return (label, row);
+        }
+        let mut marked = SpannedString::styled(Tui::gutter(row.no, bookmarks, notes), style);
+        marked.append(label);
+        (marked, row)
+    }
+
+    /// The gutter of a row with the name of its bookmark and an asterisk
+    /// if it has a note.
+    fn gutter(no: Option<usize>, bookmarks: &Bookmarks, notes: &Notes) -> String {
+        let name = no.and_then(|no| bookmarks.name(no)).unwrap_or(' ');
+        let note = match no.and_then(|no| notes.get(no)) {
+            Some(_) => '*',
+            None => ' ',
+        };
+        format!("{}{} ", name, note)
+    }
+
+    /// The local time of the newest line with a time, or the current time
+    /// without one. Times of day in filters refer to its date.
+    fn newest(buffer: &SourceBuffer<String>, formats: &Formats) -> chrono::NaiveDateTime {
+        let first = buffer.first();
+        buffer
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, s)| Tui::meta(first + i, s, formats, None).time)
+            .map_or_else(
+                || chrono::Local::now().naive_local(),
+                |t| t.with_timezone(&chrono::Local).naive_local(),
+            )
+    }
+
+    fn meta(no: usize, s: &str, formats: &Formats, previous: Option<Meta>) -> Meta {
+        Tui::parse(no, s, formats, previous).meta
+    }
+
+    /// Parses the fields of structured lines and takes the level and time
+    /// from them, detecting them in the text otherwise. Lines without them
+    /// inherit them from the previous line.
+    fn parse(no: usize, s: &str, formats: &Formats, previous: Option<Meta>) -> Parsed {
+        let (fields, message) = match formats.parse_message(s) {
+            Some((fields, message)) => (Some(fields), message),
+            None => (None, None),
+        };
+        let s = ColoredString::strip(s);
+        let level = fields
+            .as_ref()
+            .and_then(|f| f.get("level").and_then(Level::from_token))
+            .or_else(|| Level::detect(&s));
+        let time = fields
+            .as_ref()
+            .and_then(Timestamp::field)
+            .map(|(_, t)| t)
+            .or_else(|| Timestamp::detect(&s).map(|(t, _)| t));
+        let meta = Meta {
+            no,
+            level: level.or(previous.and_then(|m| m.level)),
+            time: time.or(previous.and_then(|m| m.time)),
+        };
+        Parsed {
+            meta,
+            fields,
+            message,
+        }
+    }
+
+    /// Compiles the patterns with the current flags, reporting those that
+    /// don't compile.
+    fn compile_all(patterns: &[String], flags: &Flags, error: &TextContent) -> Vec<Regex> {
+        patterns
+            .iter()
+            .filter_map(|s| match flags.compile(s) {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    error.set_content(format!("{:?}", e));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn preview(s: String, flags: &Flags, now: chrono::NaiveDateTime) -> Option<Event> {
+        if s.is_empty() {
+            Some(Event::Clear)
+        } else if Filter::new(&s, flags, now).is_ok() {
+            Some(Event::Update(s))
+        } else {
+            None
+        }
+    }
+
+    fn is_filtering(filter: &Option<Filter>, matching: Matching) -> bool {
+        matching == Matching::Filter && filter.is_some()
+    }
+
+    fn is_visible(s: &str, parsed: &Parsed, filter: &Option<Filter>, matching: Matching) -> bool {
+        match (matching, filter) {
+            (Matching::Filter, Some(f)) => f.is_match(s, parsed.meta.time, parsed.fields.as_ref()),
+            (_, _) => true,
+        }
+    }
+
     pub fn use_default_theme(&mut self) {
         self.siv
             .load_toml(include_str!("../theme/style.toml"))
@@ -396,7 +1330,8 @@ impl Tui {
                 let id = v.len() - 1;
                 v.set_selection(id);
             }
-            siv.focus_name(HISTORY_VIEW).unwrap();
+            // lets the command line cancel a search it loses focus to
+            siv.focus_name(HISTORY_VIEW).unwrap().process(siv);
             if let Some(mut v) = siv.find_name::<CommandHide>(COMMAND_HIDE) {
                 v.hide();
             }
@@ -406,69 +1341,1497 @@ impl Tui {
         }
     }
 
-    fn on_submit_command(siv: &mut Cursive, cmd: &str, tx: &std::sync::mpsc::Sender<Event>) {
+    fn on_submit_command(
+        siv: &mut Cursive,
+        cmd: &str,
+        formats: &Formats,
+        tx: &std::sync::mpsc::Sender<Event>,
+    ) {
         if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
             v.set_content("");
         }
+        let searching = siv.user_data::<State>().and_then(|s| s.searching.take());
+        if let Some(backward) = searching {
+            Tui::on_submit_search(siv, cmd, backward);
+            return;
+        }
+        if let Some(command) = cmd.strip_prefix(':') {
+            Tui::history_push(siv, cmd);
+            if let Some(mut v) = siv.find_name::<EditView>(COMMAND_VIEW) {
+                v.set_content("");
+            }
+            if let Err(e) = Tui::on_command(siv, command, formats, tx) {
+                if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                    v.set_content(format!("{:?}", e));
+                }
+            }
+            return;
+        }
+        let matching = siv
+            .user_data::<State>()
+            .map_or(Matching::Filter, |s| s.matching);
         if cmd.is_empty() {
-            tx.send(Event::Clear).unwrap();
+            match matching {
+                Matching::Filter => tx.send(Event::Clear).unwrap(),
+                Matching::Highlight => tx.send(Event::Unhighlight).unwrap(),
+            }
         } else {
-            if let Some(mut v) = siv.find_name::<SelectView>(HISTORY_VIEW) {
-                let mut id = 0;
-                for (_, value) in v.iter() {
-                    if value == cmd {
-                        break;
-                    }
-                    id += 1;
-                }
-                if id != v.len() {
-                    v.remove_item(id);
-                } else if v.len() == HISTORY_LEN {
-                    v.remove_item(0);
-                }
-
-                v.add_item_str(cmd);
-                tx.send(Event::Update(cmd.to_owned())).unwrap();
+            Tui::history_push(siv, cmd);
+            match matching {
+                Matching::Filter => tx.send(Event::Update(cmd.to_owned())).unwrap(),
+                Matching::Highlight => tx.send(Event::Highlight(cmd.to_owned())).unwrap(),
             }
             if let Some(mut v) = siv.find_name::<EditView>(COMMAND_VIEW) {
                 v.set_content("");
             }
-            if let Some(mut v) = siv.find_name::<ContentScroll>(CONTENT_SCROLL) {
-                v.set_scroll_strategy(ScrollStrategy::StickToBottom);
+        }
+    }
+
+    /// Runs a `:command` typed in the command line.
+    fn on_command(
+        siv: &mut Cursive,
+        command: &str,
+        formats: &Formats,
+        tx: &std::sync::mpsc::Sender<Event>,
+    ) -> Result<(), Error> {
+        let (name, arg) = command.split_once(' ').unwrap_or((command, ""));
+        // a trailing `!` allows commands writing files to overwrite them
+        let (name, force) = match name.strip_suffix('!') {
+            Some(name @ ("notes" | "yank" | "w" | "wa")) => (name, true),
+            _ => (name, false),
+        };
+        match name {
+            "goto" => Tui::on_goto(siv, arg.trim(), formats),
+            "reload" => Tui::on_reload(siv, tx),
+            "yank" => Tui::on_yank_file(siv, arg.trim(), force),
+            "notes" => {
+                let path = Tui::target(name, arg.trim(), force)?;
+                tx.send(Event::ExportNotes(path.to_owned())).unwrap();
+                Ok(())
+            }
+            "w" | "wa" => {
+                let path = Tui::target(name, arg.trim(), force)?;
+                match name {
+                    "w" => Tui::on_write(siv, path, formats),
+                    _ => {
+                        let palette = siv.current_theme().palette.clone();
+                        tx.send(Event::Export(path.to_owned(), palette)).unwrap();
+                        Ok(())
+                    }
+                }
             }
+            _ => Err(Error::UnknownCommand(name.to_owned())),
         }
     }
 
-    fn on_submit_history(siv: &mut Cursive, _cmd: &str) {
-        if let Some(mut v) = siv.find_name::<CommandHide>(COMMAND_HIDE) {
-            v.unhide();
+    /// Checks the file a command writes to. It has to be given and may
+    /// only exist if the command is forced.
+    fn target<'a>(command: &str, path: &'a str, force: bool) -> Result<&'a str, Error> {
+        if path.is_empty() {
+            return Err(Error::Usage(format!(":{} FILE", command)));
         }
-        if let Some(mut v) = siv.find_name::<SelectView>(HISTORY_VIEW) {
-            if let Some(id) = v.selected_id() {
-                let item = v.get_item(id).unwrap().1.clone();
-                v.remove_item(id);
-                v.add_item_str(item.clone());
-                if let Some(mut c) = siv.find_name::<EditView>(COMMAND_VIEW) {
-                    c.set_content(item);
+        if !force && std::path::Path::new(path).exists() {
+            return Err(Error::FileExists(path.to_owned(), command.to_owned()));
+        }
+        Ok(path)
+    }
+
+    /// Writes the lines of the content view to a file in the format given
+    /// by its extension.
+    fn on_write(siv: &mut Cursive, path: &str, formats: &Formats) -> Result<(), Error> {
+        let entries: Vec<Entry> = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(mut v) => {
+                let mut previous = None;
+                let mut entries = Vec::new();
+                // the styled labels are only reachable through `get_item_mut`
+                for i in 0..v.len() {
+                    if let Some((label, row)) = v.get_item_mut(i) {
+                        let meta = row.no.map(|no| Tui::meta(no, &row.text, formats, previous));
+                        previous = meta.or(previous);
+                        entries.push(Entry {
+                            no: row.no,
+                            text: row.text.clone(),
+                            label: label.clone(),
+                            level: meta.and_then(|m| m.level),
+                            time: meta.and_then(|m| m.time),
+                        });
+                    }
                 }
-                if let Some(mut h) = siv.find_name::<HistoryHide>(HISTORY_HIDE) {
-                    h.hide();
+                entries
+            }
+            None => return Ok(()),
+        };
+        let palette = siv.current_theme().palette.clone();
+        export::write(path, Format::from_path(path), &entries, formats, &palette)?;
+        if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+            let count = entries.iter().filter(|e| e.no.is_some()).count();
+            v.set_content(format!("Wrote {} lines to '{}'", count, path));
+        }
+        Ok(())
+    }
+
+    /// Reloads the highlight rules from the configuration file.
+    fn on_reload(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) -> Result<(), Error> {
+        let path = match siv.user_data::<State>().and_then(|s| s.config.clone()) {
+            Some(path) => path,
+            None => return Err(Error::NoConfig),
+        };
+        let config = Config::load(&path)?;
+        tx.send(Event::Rules(config.highlights)).unwrap();
+        Ok(())
+    }
+
+    /// Selects the first line at or after the given time.
+    fn on_goto(siv: &mut Cursive, arg: &str, formats: &Formats) -> Result<(), Error> {
+        let bound = Bound::parse(arg, false)?;
+        let times: Vec<_> = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => {
+                let mut previous = None;
+                v.iter()
+                    .enumerate()
+                    .map(|(id, (_, row))| {
+                        let meta = Tui::meta(id, &row.text, formats, previous);
+                        previous = Some(meta);
+                        meta.time
+                    })
+                    .collect()
+            }
+            None => return Ok(()),
+        };
+        // a time of day refers to the date of the last line
+        let date = times.iter().rev().find_map(|t| *t).map_or_else(
+            || chrono::Local::now().date_naive(),
+            |t| t.with_timezone(&chrono::Local).date_naive(),
+        );
+        let target = bound
+            .on(date)
+            .and_then(|bound| times.iter().position(|t| t.is_some_and(|t| t >= bound)));
+        match target {
+            Some(id) => {
+                Tui::select_content(siv, id);
+                siv.focus_name(CONTENT_VIEW).unwrap();
+            }
+            None => {
+                if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                    v.set_content("No line at or after this time");
                 }
-                siv.focus_name(COMMAND_VIEW).unwrap();
             }
         }
-        if let Some(mut v) = siv.find_name::<ContentEnableable>(CONTENT_ENABLE) {
-            v.enable();
+        Ok(())
+    }
+
+    fn on_edit_command(siv: &mut Cursive, cmd: &str, tx: &std::sync::mpsc::Sender<Event>) {
+        if let Some(state) = siv.user_data::<State>() {
+            if state.incremental
+                && state.searching.is_none()
+                && state.matching == Matching::Filter
+                && !cmd.starts_with(':')
+            {
+                tx.send(Event::Preview(cmd.to_owned())).unwrap();
+            }
         }
     }
 
-    fn quit(siv: &mut Cursive, path: &Option<String>) {
-        if let Some(p) = path {
-            if let Some(v) = siv.find_name::<SelectView>(HISTORY_VIEW) {
-                if let Ok(mut f) = std::fs::File::create(p) {
-                    if f.write_fmt(format_args!("[History]\n")).is_ok() {
-                        for item in v.iter() {
-                            if f.write_fmt(format_args!("{}\n", item.0)).is_err() {
+    fn on_toggle_incremental(siv: &mut Cursive) {
+        let indicators = match siv.user_data::<State>() {
+            Some(state) => {
+                state.incremental = !state.incremental;
+                state.indicators()
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(FLAGS_VIEW) {
+            v.set_content(indicators);
+        }
+    }
+
+    fn on_toggle_flag(
+        siv: &mut Cursive,
+        tx: &std::sync::mpsc::Sender<Event>,
+        toggle: impl Fn(&mut Flags),
+    ) {
+        let (flags, indicators) = match siv.user_data::<State>() {
+            Some(state) => {
+                toggle(&mut state.flags);
+                (state.flags, state.indicators())
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(FLAGS_VIEW) {
+            v.set_content(indicators);
+        }
+        tx.send(Event::Flags(flags)).unwrap();
+    }
+
+    fn on_min_level(siv: &mut Cursive, level: Level, tx: &std::sync::mpsc::Sender<Event>) {
+        let indicators = match siv.user_data::<State>() {
+            Some(state) => {
+                state.level = level;
+                state.indicators()
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(FLAGS_VIEW) {
+            v.set_content(indicators);
+        }
+        tx.send(Event::Level(level)).unwrap();
+    }
+
+    fn on_cycle_time(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let (time, indicators) = match siv.user_data::<State>() {
+            Some(state) => {
+                state.time = state.time.next();
+                (state.time, state.indicators())
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(FLAGS_VIEW) {
+            v.set_content(indicators);
+        }
+        tx.send(Event::Time(time)).unwrap();
+    }
+
+    /// Measures relative times from the selected line.
+    fn on_set_reference(siv: &mut Cursive, formats: &Formats, tx: &std::sync::mpsc::Sender<Event>) {
+        let line = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.selection(),
+            None => return,
+        };
+        let time = match line.and_then(|r| Tui::meta(0, &r.text, formats, None).time) {
+            Some(t) => t,
+            None => return,
+        };
+        let indicators = match siv.user_data::<State>() {
+            Some(state) => {
+                state.time = TimeMode::Relative;
+                state.indicators()
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(FLAGS_VIEW) {
+            v.set_content(indicators);
+        }
+        tx.send(Event::Reference(time)).unwrap();
+        tx.send(Event::Time(TimeMode::Relative)).unwrap();
+    }
+
+    /// Opens a table of the structured lines in the content view.
+    fn on_open_table(siv: &mut Cursive, formats: &Formats, fields: &[String]) {
+        let rows = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v
+                .iter()
+                .filter_map(|(_, row)| {
+                    let no = row.no?;
+                    formats
+                        .parse(&ColoredString::strip(&row.text))
+                        .map(|f| (no, f))
+                })
+                .collect(),
+            None => return,
+        };
+        let table = Table::new(rows, fields);
+        if table.is_empty() {
+            if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                v.set_content("No structured lines to show");
+            }
+            return;
+        }
+        match siv.user_data::<State>() {
+            Some(state) => state.table = Some(table),
+            None => return,
+        }
+        siv.add_fullscreen_layer(
+            Panel::new(
+                OnEventView::new(
+                    LinearLayout::vertical()
+                        .child(TextView::new("").no_wrap().with_name(TABLE_HEADER))
+                        .child(
+                            SelectView::<usize>::new()
+                                .on_submit(Tui::on_submit_table)
+                                .with_name(TABLE_VIEW)
+                                .scrollable()
+                                .full_height(),
+                        )
+                        .scrollable()
+                        .scroll_x(true)
+                        .scroll_y(false),
+                )
+                .on_pre_event('h', |s| Tui::on_change_table(s, |t| t.select(-1)))
+                .on_pre_event('l', |s| Tui::on_change_table(s, |t| t.select(1)))
+                .on_pre_event('<', |s| Tui::on_change_table(s, |t| t.resize(-2)))
+                .on_pre_event('>', |s| Tui::on_change_table(s, |t| t.resize(2)))
+                .on_pre_event('H', |s| Tui::on_change_table(s, |t| t.shift(-1)))
+                .on_pre_event('L', |s| Tui::on_change_table(s, |t| t.shift(1)))
+                .on_pre_event('x', |s| Tui::on_change_table(s, Table::hide))
+                .on_pre_event('a', |s| Tui::on_change_table(s, Table::show_all))
+                .on_pre_event('s', |s| Tui::on_change_table(s, Table::sort))
+                .on_pre_event('q', Tui::on_close_table)
+                .on_pre_event(Key::Esc, Tui::on_close_table),
+            )
+            .title("Table")
+            .full_screen(),
+        );
+        Tui::refresh_table(siv);
+    }
+
+    fn on_change_table(siv: &mut Cursive, change: impl Fn(&mut Table)) {
+        if let Some(table) = siv.user_data::<State>().and_then(|s| s.table.as_mut()) {
+            change(table);
+        }
+        Tui::refresh_table(siv);
+    }
+
+    fn refresh_table(siv: &mut Cursive) {
+        let (header, rows) = match siv.user_data::<State>().and_then(|s| s.table.as_ref()) {
+            Some(table) => (table.header(), table.rows()),
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(TABLE_HEADER) {
+            v.set_content(header);
+        }
+        if let Some(mut v) = siv.find_name::<SelectView<usize>>(TABLE_VIEW) {
+            let selected = v.selection().map(|id| *id);
+            v.clear();
+            v.add_all(rows);
+            if let Some(pos) = selected.and_then(|id| v.iter().position(|(_, row)| *row == id)) {
+                v.set_selection(pos);
+            }
+        }
+    }
+
+    /// Closes the table and selects the line in the content view.
+    fn on_submit_table(siv: &mut Cursive, no: &usize) {
+        Tui::on_close_table(siv);
+        // the view may have been rebuilt since the table was opened
+        let target = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.iter().position(|(_, row)| row.no == Some(*no)),
+            None => return,
+        };
+        match target {
+            Some(id) => {
+                Tui::select_content(siv, id);
+                siv.focus_name(CONTENT_VIEW).unwrap();
+            }
+            None => {
+                if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                    v.set_content("Line isn't shown anymore");
+                }
+            }
+        }
+    }
+
+    fn on_close_table(siv: &mut Cursive) {
+        siv.pop_layer();
+        if let Some(state) = siv.user_data::<State>() {
+            state.table = None;
+        }
+    }
+
+    /// Shows the full line and its fields below the content view.
+    fn on_open_detail(siv: &mut Cursive, row: &Row, formats: &Formats) {
+        if row.text.is_empty() {
+            return;
+        }
+        let line = ColoredString::strip(&row.text);
+        let tree = formats.parse(&line).map(|f| Tree::new(&f));
+        let note = match siv.user_data::<State>() {
+            Some(state) => {
+                state.detail = tree;
+                row.no
+                    .and_then(|no| state.notes.get(no))
+                    .map(|n| n.text.clone())
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(DETAIL_TEXT) {
+            match note {
+                Some(note) => v.set_content(format!("{}\n\nNote: {}", line.trim_end(), note)),
+                None => v.set_content(line.trim_end()),
+            }
+        }
+        if let Some(mut v) = siv.find_name::<DetailHide>(DETAIL_HIDE) {
+            v.unhide();
+        }
+        if Tui::refresh_detail(siv) {
+            siv.focus_name(DETAIL_VIEW).unwrap();
+        }
+    }
+
+    /// Shows the rows of the field tree. Returns `false` if there are none.
+    fn refresh_detail(siv: &mut Cursive) -> bool {
+        let (key, value) = (
+            Tui::custom_style(siv, "field_key"),
+            Tui::custom_style(siv, "field_value"),
+        );
+        let rows = siv
+            .user_data::<State>()
+            .and_then(|s| s.detail.as_ref())
+            .map(|t| t.rows(key, value))
+            .unwrap_or_default();
+        let found = !rows.is_empty();
+        if let Some(mut v) = siv.find_name::<SelectView<Vec<usize>>>(DETAIL_VIEW) {
+            let selected = v.selected_id();
+            v.clear();
+            v.add_all(rows);
+            if let Some(id) = selected {
+                v.set_selection(id);
+            }
+        }
+        found
+    }
+
+    fn on_toggle_detail(siv: &mut Cursive, path: &[usize]) {
+        if let Some(tree) = siv.user_data::<State>().and_then(|s| s.detail.as_mut()) {
+            tree.toggle(path);
+        }
+        Tui::refresh_detail(siv);
+    }
+
+    fn on_copy_detail(siv: &mut Cursive) {
+        let path = match siv.find_name::<SelectView<Vec<usize>>>(DETAIL_VIEW) {
+            Some(v) => v.selection(),
+            None => return,
+        };
+        let value = siv
+            .user_data::<State>()
+            .and_then(|s| s.detail.as_ref())
+            .zip(path)
+            .and_then(|(t, p)| t.value(&p));
+        if let Some(value) = value {
+            let message = match Tui::copy(siv, &value) {
+                Ok(_) => "Copied".to_owned(),
+                Err(e) => format!("{:?}", e),
+            };
+            if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                v.set_content(message);
+            }
+        }
+    }
+
+    /// Filters on the value of the selected field.
+    fn on_filter_detail(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let path = match siv.find_name::<SelectView<Vec<usize>>>(DETAIL_VIEW) {
+            Some(v) => v.selection(),
+            None => return,
+        };
+        let filter = siv
+            .user_data::<State>()
+            .and_then(|s| s.detail.as_ref())
+            .zip(path)
+            .and_then(|(t, p)| t.filter(&p));
+        match filter {
+            Some(filter) => {
+                Tui::history_push(siv, &filter);
+                tx.send(Event::Refine(filter)).unwrap();
+                Tui::on_close_detail(siv);
+            }
+            None => {
+                if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                    v.set_content("Can't filter on this value");
+                }
+            }
+        }
+    }
+
+    fn on_close_detail(siv: &mut Cursive) {
+        if let Some(mut v) = siv.find_name::<DetailHide>(DETAIL_HIDE) {
+            v.hide();
+        }
+        if let Some(state) = siv.user_data::<State>() {
+            state.detail = None;
+        }
+        siv.focus_name(CONTENT_VIEW).unwrap();
+    }
+
+    /// Opens the list of semantic highlighters, where Enter toggles one.
+    fn on_open_semantic(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let tx = tx.clone();
+        siv.add_layer(
+            OnEventView::new(
+                Dialog::around(
+                    SelectView::<Class>::new()
+                        .on_submit(move |s, class: &Class| Tui::on_toggle_semantic(s, *class, &tx))
+                        .with_name(SEMANTIC_VIEW),
+                )
+                .title("Highlighters")
+                .button("Close", |s| {
+                    s.pop_layer();
+                }),
+            )
+            .on_pre_event(Key::Esc, |s| {
+                s.pop_layer();
+            }),
+        );
+        Tui::refresh_semantic(siv);
+    }
+
+    fn on_toggle_semantic(siv: &mut Cursive, class: Class, tx: &std::sync::mpsc::Sender<Event>) {
+        let (enabled, indicators) = match siv.user_data::<State>() {
+            Some(state) => {
+                match state.semantic.iter().position(|c| *c == class) {
+                    Some(pos) => {
+                        state.semantic.remove(pos);
+                    }
+                    None => state.semantic.push(class),
+                }
+                (state.semantic.clone(), state.indicators())
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(FLAGS_VIEW) {
+            v.set_content(indicators);
+        }
+        Tui::refresh_semantic(siv);
+        tx.send(Event::Semantic(enabled)).unwrap();
+    }
+
+    fn refresh_semantic(siv: &mut Cursive) {
+        let enabled = match siv.user_data::<State>() {
+            Some(state) => state.semantic.clone(),
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<SelectView<Class>>(SEMANTIC_VIEW) {
+            let selected = v.selected_id();
+            v.clear();
+            for class in Class::ALL.iter() {
+                let check = if enabled.contains(class) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                v.add_item(format!("{} {}", check, class.name()), *class);
+            }
+            if let Some(id) = selected {
+                v.set_selection(id);
+            }
+        }
+    }
+
+    /// Waits for a letter and passes it on, e.g. to name a mark.
+    fn prompt_letter(
+        siv: &mut Cursive,
+        title: &str,
+        on_letter: impl Fn(&mut Cursive, char) + 'static,
+    ) {
+        let on_letter = std::rc::Rc::new(on_letter);
+        siv.add_layer(
+            OnEventView::new(Dialog::text("Press a letter").title(title))
+                .on_pre_event_inner(
+                    EventTrigger::from_fn(
+                        |e| matches!(e, CursiveEvent::Char(c) if c.is_ascii_alphabetic()),
+                    ),
+                    move |_, e| {
+                        let name = match e {
+                            CursiveEvent::Char(c) => *c,
+                            _ => return None,
+                        };
+                        let on_letter = on_letter.clone();
+                        Some(EventResult::with_cb(move |s| {
+                            s.pop_layer();
+                            on_letter(s, name);
+                        }))
+                    },
+                )
+                .on_pre_event(Key::Esc, |s| {
+                    s.pop_layer();
+                }),
+        );
+    }
+
+    fn on_set_mark(siv: &mut Cursive, name: char, tx: &std::sync::mpsc::Sender<Event>) {
+        let row = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.selection(),
+            None => return,
+        };
+        let (no, text) = match row.as_deref() {
+            Some(Row {
+                no: Some(no), text, ..
+            }) => (*no, ColoredString::strip(text)),
+            _ => return,
+        };
+        if let Some(state) = siv.user_data::<State>() {
+            state.bookmarks.set(name, no, text.trim_end().to_owned());
+        }
+        Tui::refresh_bookmarks(siv, tx);
+    }
+
+    fn on_jump_mark(siv: &mut Cursive, name: char) {
+        let no = match siv.user_data::<State>().and_then(|s| s.bookmarks.get(name)) {
+            Some(mark) => mark.no,
+            None => {
+                if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                    v.set_content(format!("Mark '{}' isn't set", name));
+                }
+                return;
+            }
+        };
+        let target = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.iter().position(|(_, row)| row.no == Some(no)),
+            None => return,
+        };
+        match target {
+            Some(id) => {
+                Tui::center_content(siv, id);
+                siv.focus_name(CONTENT_VIEW).unwrap();
+            }
+            None => {
+                if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                    v.set_content(format!("Line of mark '{}' isn't shown", name));
+                }
+            }
+        }
+    }
+
+    /// Cycles through the marked lines shown in the content view.
+    fn on_next_mark(siv: &mut Cursive, backward: bool) {
+        let bookmarks = match siv.user_data::<State>() {
+            Some(state) => state.bookmarks.clone(),
+            None => return,
+        };
+        let target = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => {
+                let marked: Vec<usize> = v
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, row))| row.no.and_then(|no| bookmarks.name(no)).is_some())
+                    .map(|(id, _)| id)
+                    .collect();
+                let current = v.selected_id().unwrap_or(0);
+                if backward {
+                    marked
+                        .iter()
+                        .rev()
+                        .find(|id| **id < current)
+                        .or(marked.last())
+                } else {
+                    marked.iter().find(|id| **id > current).or(marked.first())
+                }
+                .copied()
+            }
+            None => return,
+        };
+        match target {
+            Some(id) => Tui::center_content(siv, id),
+            None => {
+                if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                    v.set_content("No marked lines shown");
+                }
+            }
+        }
+    }
+
+    fn on_delete_mark(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let name = match siv.find_name::<SelectView<char>>(BOOKMARK_VIEW) {
+            Some(v) => v.selection(),
+            None => return,
+        };
+        if let (Some(name), Some(state)) = (name, siv.user_data::<State>()) {
+            state.bookmarks.remove(*name);
+            if state.bookmarks.is_empty() {
+                siv.focus_name(CONTENT_VIEW).unwrap();
+            }
+        }
+        Tui::refresh_bookmarks(siv, tx);
+    }
+
+    /// Opens an editor for the note on the selected line. Saving an
+    /// empty note removes it.
+    fn on_open_note(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let row = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.selection(),
+            None => return,
+        };
+        let (no, line) = match row.as_deref() {
+            Some(Row {
+                no: Some(no), text, ..
+            }) => (*no, ColoredString::strip(text)),
+            _ => return,
+        };
+        let note = siv
+            .user_data::<State>()
+            .and_then(|s| s.notes.get(no))
+            .map(|n| n.text.clone())
+            .unwrap_or_default();
+        let line = line.trim_end().to_owned();
+        let (submit_tx, save_tx) = (tx.clone(), tx.clone());
+        let submit_line = line.clone();
+        siv.add_layer(
+            OnEventView::new(
+                Dialog::around(
+                    EditView::new()
+                        .content(note)
+                        .on_submit(move |s, text| {
+                            Tui::on_save_note(s, no, &submit_line, text, &submit_tx)
+                        })
+                        .with_name(NOTE_VIEW)
+                        .min_width(60),
+                )
+                .title("Note")
+                .button("Save", move |s| {
+                    let text = match s.find_name::<EditView>(NOTE_VIEW) {
+                        Some(v) => v.get_content(),
+                        None => return,
+                    };
+                    Tui::on_save_note(s, no, &line, &text, &save_tx)
+                })
+                .button("Cancel", |s| {
+                    s.pop_layer();
+                }),
+            )
+            .on_pre_event(Key::Esc, |s| {
+                s.pop_layer();
+            }),
+        );
+    }
+
+    fn on_save_note(
+        siv: &mut Cursive,
+        no: usize,
+        line: &str,
+        text: &str,
+        tx: &std::sync::mpsc::Sender<Event>,
+    ) {
+        siv.pop_layer();
+        let notes = match siv.user_data::<State>() {
+            Some(state) => {
+                state.notes.set(no, line.to_owned(), text.trim().to_owned());
+                state.notes.clone()
+            }
+            None => return,
+        };
+        Tui::refresh_gutter(siv);
+        tx.send(Event::Notes(notes)).unwrap();
+    }
+
+    /// Copies text with the configured command, or with OSC 52 otherwise.
+    fn copy(siv: &mut Cursive, text: &str) -> Result<(), Error> {
+        let command = siv
+            .user_data::<State>()
+            .and_then(|s| s.copy_command.clone());
+        match command {
+            Some(command) => Clipboard::pipe(&command, text),
+            None => Clipboard::copy(text),
+        }
+    }
+
+    /// Starts a range of lines at the selected line, or drops it.
+    fn on_toggle_visual(siv: &mut Cursive) {
+        let no = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.selection().and_then(|r| r.no),
+            None => return,
+        };
+        if let Some(state) = siv.user_data::<State>() {
+            state.visual = match state.visual {
+                Some(_) => None,
+                None => no,
+            };
+        }
+        Tui::refresh_visual(siv);
+    }
+
+    /// Counts the lines in the visual range after the selection moved.
+    fn refresh_visual(siv: &mut Cursive) {
+        let visual = siv.user_data::<State>().and_then(|s| s.visual);
+        let selected = match (visual, siv.find_name::<SelectView<Row>>(CONTENT_VIEW)) {
+            (Some(_), Some(v)) => Tui::visual_range(&v, visual).map_or(0, |range| {
+                v.iter()
+                    .skip(range.start)
+                    .take(range.len())
+                    .filter(|(_, row)| row.no.is_some())
+                    .count()
+            }),
+            (_, _) => 0,
+        };
+        let indicators = match siv.user_data::<State>() {
+            Some(state) => {
+                state.selected = selected;
+                state.indicators()
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(FLAGS_VIEW) {
+            v.set_content(indicators);
+        }
+    }
+
+    /// Rows from the one closest to the start of the visual range to the
+    /// selected row, or just the selected row.
+    fn visual_range(v: &SelectView<Row>, visual: Option<usize>) -> Option<Range<usize>> {
+        let current = v.selected_id()?;
+        let start = visual
+            .and_then(|start| {
+                v.iter()
+                    .enumerate()
+                    .filter_map(|(id, (_, row))| row.no.map(|no| (id, no)))
+                    .min_by_key(|(_, no)| no.abs_diff(start))
+                    .map(|(id, _)| id)
+            })
+            .unwrap_or(current);
+        Some(start.min(current)..start.max(current) + 1)
+    }
+
+    /// Takes the lines from the start of the range to the selected line,
+    /// or just the selected line, without escape sequences. Ends the range.
+    fn take_selection(siv: &mut Cursive) -> Vec<String> {
+        let (visual, indicators) = match siv.user_data::<State>() {
+            Some(state) => (state.visual.take(), state.indicators()),
+            None => return Vec::new(),
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(FLAGS_VIEW) {
+            v.set_content(indicators);
+        }
+        let v = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+        let range = match Tui::visual_range(&v, visual) {
+            Some(range) => range,
+            None => return Vec::new(),
+        };
+        v.iter()
+            .skip(range.start)
+            .take(range.len())
+            .filter(|(_, row)| row.no.is_some())
+            .map(|(_, row)| ColoredString::strip(&row.text).trim_end().to_owned())
+            .collect()
+    }
+
+    /// Copies the selected line or range of lines.
+    fn on_yank(siv: &mut Cursive) {
+        let lines = Tui::take_selection(siv);
+        if lines.is_empty() {
+            return;
+        }
+        let message = match Tui::copy(siv, &lines.join("\n")) {
+            Ok(_) => format!("Copied {} lines", lines.len()),
+            Err(e) => format!("{:?}", e),
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+            v.set_content(message);
+        }
+    }
+
+    /// Writes the selected line or range of lines to a file.
+    fn on_yank_file(siv: &mut Cursive, path: &str, force: bool) -> Result<(), Error> {
+        let path = Tui::target("yank", path, force)?;
+        let lines = Tui::take_selection(siv);
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let mut text = lines.join("\n");
+        text.push('\n');
+        std::fs::write(path, text).map_err(|_| Error::WriteFailed(path.to_owned()))?;
+        if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+            v.set_content(format!("Wrote {} lines to '{}'", lines.len(), path));
+        }
+        Ok(())
+    }
+
+    /// Pins the selected line above the content view, or unpins it.
+    fn on_toggle_pin(siv: &mut Cursive) {
+        let row = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.selection(),
+            None => return,
+        };
+        let (no, line) = match row.as_deref() {
+            Some(Row {
+                no: Some(no), text, ..
+            }) => (*no, ColoredString::strip(text)),
+            _ => return,
+        };
+        if let Some(state) = siv.user_data::<State>() {
+            match state.pins.iter().position(|(n, _)| *n == no) {
+                Some(pos) => {
+                    state.pins.remove(pos);
+                }
+                None => state.pins.push((no, line.trim_end().to_owned())),
+            }
+        }
+        Tui::refresh_pins(siv, None);
+    }
+
+    fn on_jump_pin(siv: &mut Cursive, no: usize) {
+        let target = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.iter().position(|(_, row)| row.no == Some(no)),
+            None => return,
+        };
+        match target {
+            Some(id) => {
+                Tui::center_content(siv, id);
+                siv.focus_name(CONTENT_VIEW).unwrap();
+            }
+            None => {
+                if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                    v.set_content("Pinned line isn't shown");
+                }
+            }
+        }
+    }
+
+    fn on_delete_pin(siv: &mut Cursive) {
+        let id = match siv.find_name::<SelectView<usize>>(PIN_VIEW) {
+            Some(v) => v.selected_id(),
+            None => return,
+        };
+        if let (Some(id), Some(state)) = (id, siv.user_data::<State>()) {
+            state.pins.remove(id);
+        }
+        Tui::refresh_pins(siv, None);
+    }
+
+    /// Moves the selected pinned line up or down.
+    fn on_move_pin(siv: &mut Cursive, up: bool) {
+        let id = match siv.find_name::<SelectView<usize>>(PIN_VIEW) {
+            Some(v) => v.selected_id(),
+            None => return,
+        };
+        let moved = match (id, siv.user_data::<State>()) {
+            (Some(id), Some(state)) => {
+                let to = if up { id.checked_sub(1) } else { Some(id + 1) };
+                match to.filter(|to| *to < state.pins.len()) {
+                    Some(to) => {
+                        state.pins.swap(id, to);
+                        Some(to)
+                    }
+                    None => None,
+                }
+            }
+            _ => None,
+        };
+        if moved.is_some() {
+            Tui::refresh_pins(siv, moved);
+        }
+    }
+
+    /// Moves the focus from a side panel back to the content view.
+    fn on_leave_panel(siv: &mut Cursive) {
+        siv.focus_name(CONTENT_VIEW).unwrap();
+    }
+
+    fn refresh_pins(siv: &mut Cursive, select: Option<usize>) {
+        let pins = match siv.user_data::<State>() {
+            Some(state) => state.pins.clone(),
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<SelectView<usize>>(PIN_VIEW) {
+            let selected = select.or(v.selected_id()).unwrap_or(0);
+            v.clear();
+            for (no, line) in pins.iter() {
+                v.add_item(format!("{:>6} {}", no + 1, line), *no);
+            }
+            if !pins.is_empty() {
+                v.set_selection(selected.min(pins.len() - 1));
+            }
+        }
+        if let Some(mut v) = siv.find_name::<PinHide>(PIN_HIDE) {
+            v.set_visible(!pins.is_empty());
+        }
+        if pins.is_empty() {
+            siv.focus_name(CONTENT_VIEW).unwrap();
+        }
+    }
+
+    fn on_toggle_bookmarks(siv: &mut Cursive) {
+        let empty = siv
+            .user_data::<State>()
+            .is_none_or(|s| s.bookmarks.is_empty());
+        let visible = match siv.find_name::<BookmarkHide>(BOOKMARK_HIDE) {
+            Some(mut v) => {
+                let visible = !v.is_visible() && !empty;
+                v.set_visible(visible);
+                visible
+            }
+            None => return,
+        };
+        if visible {
+            siv.focus_name(BOOKMARK_VIEW).unwrap();
+        } else {
+            siv.focus_name(CONTENT_VIEW).unwrap();
+        }
+    }
+
+    fn on_close_bookmarks(siv: &mut Cursive) {
+        if let Some(mut v) = siv.find_name::<BookmarkHide>(BOOKMARK_HIDE) {
+            v.hide();
+        }
+        siv.focus_name(CONTENT_VIEW).unwrap();
+    }
+
+    fn refresh_bookmarks(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let bookmarks = match siv.user_data::<State>() {
+            Some(state) => state.bookmarks.clone(),
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<SelectView<char>>(BOOKMARK_VIEW) {
+            let selected = v.selected_id().unwrap_or(0);
+            v.clear();
+            for mark in bookmarks.iter() {
+                v.add_item(
+                    format!("{} {:>6} {}", mark.name, mark.no + 1, mark.text),
+                    mark.name,
+                );
+            }
+            if !bookmarks.is_empty() {
+                v.set_selection(selected.min(bookmarks.len() - 1));
+            }
+        }
+        if bookmarks.is_empty() {
+            if let Some(mut v) = siv.find_name::<BookmarkHide>(BOOKMARK_HIDE) {
+                v.hide();
+            }
+        }
+        Tui::refresh_gutter(siv);
+        tx.send(Event::Bookmarks(bookmarks)).unwrap();
+    }
+
+    /// Updates the gutter of the shown rows after a bookmark or note
+    /// changed, without rebuilding the view.
+    fn refresh_gutter(siv: &mut Cursive) {
+        let (bookmarks, notes) = match siv.user_data::<State>() {
+            Some(state) => (state.bookmarks.clone(), state.notes.clone()),
+            None => return,
+        };
+        let style = Tui::custom_style(siv, "bookmark");
+        let show = !(bookmarks.is_empty() && notes.is_empty());
+        if let Some(mut v) = siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            for i in 0..v.len() {
+                if let Some((label, row)) = v.get_item_mut(i) {
+                    let text = Tui::gutter(row.no, &bookmarks, &notes);
+                    let current = std::mem::replace(label, SpannedString::new());
+                    // the gutter is the first span of the label
+                    let end = current.spans().next().map_or(0, |s| s.content.len());
+                    *label = match (row.gutter, show) {
+                        (true, true) => ColoredString::replace(current, 0..end, &text),
+                        (true, false) => ColoredString::replace(current, 0..end, ""),
+                        (false, true) => {
+                            let mut marked = SpannedString::styled(text, style);
+                            marked.append(current);
+                            marked
+                        }
+                        (false, false) => current,
+                    };
+                    row.gutter = show;
+                }
+            }
+        }
+    }
+
+    fn on_add_mute(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let pattern = match siv.find_name::<EditView>(COMMAND_VIEW) {
+            Some(mut v) => {
+                let pattern = v.get_content().to_string();
+                v.set_content("");
+                pattern
+            }
+            None => return,
+        };
+        if pattern.is_empty() {
+            return;
+        }
+        if let Some(state) = siv.user_data::<State>() {
+            if !state.mutes.iter().any(|(p, _)| *p == pattern) {
+                state.mutes.push((pattern, true));
+            }
+        }
+        Tui::refresh_mutes(siv, tx);
+    }
+
+    fn on_toggle_mute(siv: &mut Cursive, pattern: &str, tx: &std::sync::mpsc::Sender<Event>) {
+        if let Some(state) = siv.user_data::<State>() {
+            for (p, enabled) in state.mutes.iter_mut() {
+                if p == pattern {
+                    *enabled = !*enabled;
+                }
+            }
+        }
+        Tui::refresh_mutes(siv, tx);
+    }
+
+    fn on_delete_mute(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let id = match siv.find_name::<SelectView>(MUTE_VIEW) {
+            Some(v) => v.selected_id(),
+            None => return,
+        };
+        if let (Some(id), Some(state)) = (id, siv.user_data::<State>()) {
+            state.mutes.remove(id);
+            if state.mutes.is_empty() {
+                siv.focus_name(COMMAND_VIEW).unwrap();
+            }
+        }
+        Tui::refresh_mutes(siv, tx);
+    }
+
+    fn refresh_mutes(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let mutes = match siv.user_data::<State>() {
+            Some(state) => state.mutes.clone(),
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<SelectView>(MUTE_VIEW) {
+            let selected = v.selected_id().unwrap_or(0);
+            v.clear();
+            for (pattern, enabled) in mutes.iter() {
+                let mark = if *enabled { "[x] " } else { "[ ] " };
+                v.add_item(format!("{}{}", mark, pattern), pattern.clone());
+            }
+            if !mutes.is_empty() {
+                v.set_selection(selected.min(mutes.len() - 1));
+            }
+        }
+        if let Some(mut v) = siv.find_name::<MuteHide>(MUTE_HIDE) {
+            v.set_visible(!mutes.is_empty());
+        }
+        tx.send(Event::Mute(
+            mutes
+                .into_iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(pattern, _)| pattern)
+                .collect(),
+        ))
+        .unwrap();
+    }
+
+    fn history_push(siv: &mut Cursive, cmd: &str) {
+        if let Some(mut v) = siv.find_name::<SelectView>(HISTORY_VIEW) {
+            let mut id = 0;
+            for (_, value) in v.iter() {
+                if value == cmd {
+                    break;
+                }
+                id += 1;
+            }
+            if id != v.len() {
+                v.remove_item(id);
+            } else if v.len() == HISTORY_LEN {
+                v.remove_item(0);
+            }
+
+            v.add_item_str(cmd);
+        }
+    }
+
+    fn on_start_search(siv: &mut Cursive, backward: bool) {
+        if let Some(state) = siv.user_data::<State>() {
+            state.searching = Some(backward);
+        }
+        if let Some(mut v) = siv.find_name::<TextView>(PROMPT_VIEW) {
+            v.set_content(if backward { "?" } else { "/" });
+        }
+        siv.focus_name(COMMAND_VIEW).unwrap();
+    }
+
+    /// Leaves the search prompt without searching.
+    fn on_cancel_search(siv: &mut Cursive) {
+        let matching = match siv.user_data::<State>() {
+            Some(state) => match state.searching.take() {
+                Some(_) => state.matching,
+                None => return,
+            },
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(PROMPT_VIEW) {
+            v.set_content(matching.prompt());
+        }
+        if let Some(mut v) = siv.find_name::<EditView>(COMMAND_VIEW) {
+            v.set_content("");
+        }
+    }
+
+    fn on_submit_search(siv: &mut Cursive, cmd: &str, backward: bool) {
+        let matching = siv
+            .user_data::<State>()
+            .map_or(Matching::Filter, |s| s.matching);
+        if let Some(mut v) = siv.find_name::<TextView>(PROMPT_VIEW) {
+            v.set_content(matching.prompt());
+        }
+        if let Some(mut v) = siv.find_name::<EditView>(COMMAND_VIEW) {
+            v.set_content("");
+        }
+        if !cmd.is_empty() {
+            let flags = siv
+                .user_data::<State>()
+                .map_or_else(Flags::default, |s| s.flags);
+            match flags.compile(cmd) {
+                Ok(regex) => {
+                    Tui::history_push(siv, cmd);
+                    if let Some(state) = siv.user_data::<State>() {
+                        state.search = Some(Search { regex, backward });
+                    }
+                }
+                Err(e) => {
+                    if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                        v.set_content(format!("{:?}", e));
+                    }
+                    return;
+                }
+            }
+        }
+        siv.focus_name(CONTENT_VIEW).unwrap();
+        Tui::on_search_next(siv, false);
+    }
+
+    /// Selects a line of the content view and scrolls it into view.
+    fn select_content(siv: &mut Cursive, id: usize) {
+        Tui::set_follow(siv, false);
+        if let Some(mut v) = siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            v.set_selection(id)(siv);
+        }
+        if let Some(mut v) = siv.find_name::<ContentScroll>(CONTENT_SCROLL) {
+            v.scroll_to_important_area();
+        }
+    }
+
+    fn on_search_next(siv: &mut Cursive, reverse: bool) {
+        let (regex, backward) = match siv.user_data::<State>().and_then(|s| s.search.as_ref()) {
+            Some(search) => (search.regex.clone(), search.backward != reverse),
+            None => return,
+        };
+        let (target, counter) = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => {
+                let matches: Vec<usize> = v
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, row))| regex.is_match(&ColoredString::strip(&row.text)))
+                    .map(|(id, _)| id)
+                    .collect();
+                let current = v.selected_id().unwrap_or(0);
+                let pos = if backward {
+                    matches
+                        .iter()
+                        .rposition(|id| *id < current)
+                        .or_else(|| matches.len().checked_sub(1))
+                } else {
+                    matches
+                        .iter()
+                        .position(|id| *id > current)
+                        .or(if matches.is_empty() { None } else { Some(0) })
+                };
+                match pos {
+                    Some(pos) => (Some(matches[pos]), format!("{}/{}", pos + 1, matches.len())),
+                    None => (None, "0/0".to_owned()),
+                }
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(SEARCH_VIEW) {
+            v.set_content(counter);
+        }
+        match target {
+            Some(id) => Tui::select_content(siv, id),
+            None => {
+                if let Some(mut v) = siv.find_name::<TextView>(ERROR_VIEW) {
+                    v.set_content("Pattern not found");
+                }
+            }
+        }
+    }
+
+    /// Remembers the selected line before the content view is rebuilt,
+    /// unless the view follows new lines.
+    fn anchor_content(siv: &mut Cursive) {
+        let row = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.selection(),
+            None => return,
+        };
+        if let Some(state) = siv.user_data::<State>() {
+            if !state.follow && state.anchor.is_none() {
+                state.anchor = row.and_then(|r| r.no);
+            }
+        }
+    }
+
+    /// Selects and centers the anchored line once the content view is
+    /// rebuilt, or the nearest line if it isn't shown anymore.
+    fn restore_anchor(siv: &mut Cursive) {
+        let anchor = match siv.user_data::<State>().and_then(|s| s.anchor.take()) {
+            Some(anchor) => anchor,
+            None => return,
+        };
+        let target = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v
+                .iter()
+                .enumerate()
+                .filter_map(|(id, (_, row))| row.no.map(|no| (id, no)))
+                .min_by_key(|(_, no)| no.abs_diff(anchor))
+                .map(|(id, _)| id),
+            None => return,
+        };
+        if let Some(id) = target {
+            Tui::center_content(siv, id);
+        }
+    }
+
+    /// Selects a line of the content view and scrolls it to the center.
+    fn center_content(siv: &mut Cursive, id: usize) {
+        Tui::select_content(siv, id);
+        // the offset is clamped to the content of the last layout, which may
+        // be from before the view was rebuilt
+        let size = siv.screen_size();
+        siv.screen_mut().layout(size);
+        if let Some(mut v) = siv.find_name::<ContentScroll>(CONTENT_SCROLL) {
+            let viewport = v.content_viewport();
+            v.set_offset((viewport.left(), id.saturating_sub(viewport.height() / 2)));
+        }
+    }
+
+    /// Sticks the content view to the last line like `F` in less, or
+    /// keeps the current row while new lines arrive.
+    fn set_follow(siv: &mut Cursive, follow: bool) {
+        match siv.user_data::<State>() {
+            Some(state) => state.follow = follow,
+            None => return,
+        }
+        if follow {
+            if let Some(mut v) = siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+                if let Some(last) = v.len().checked_sub(1) {
+                    v.set_selection(last)(siv);
+                }
+            }
+        }
+        if let Some(mut v) = siv.find_name::<ContentScroll>(CONTENT_SCROLL) {
+            if follow {
+                v.scroll_to_bottom();
+                v.set_scroll_strategy(ScrollStrategy::StickToBottom);
+            } else {
+                v.set_scroll_strategy(ScrollStrategy::KeepRow);
+            }
+        }
+        Tui::refresh_follow(siv);
+    }
+
+    /// Stops following once the view is scrolled up or the selection
+    /// leaves the last line.
+    fn on_scroll_content(siv: &mut Cursive, bottom: bool) {
+        let last = match siv.find_name::<SelectView<Row>>(CONTENT_VIEW) {
+            Some(v) => v.selected_id().is_none_or(|id| id + 1 >= v.len()),
+            None => return,
+        };
+        let follow = siv.user_data::<State>().is_some_and(|s| s.follow);
+        if follow && !(bottom && last) {
+            Tui::set_follow(siv, false);
+        }
+    }
+
+    fn refresh_follow(siv: &mut Cursive) {
+        let follow = siv.user_data::<State>().is_some_and(|s| s.follow);
+        if let Some(mut v) = siv.find_name::<TextView>(FOLLOW_VIEW) {
+            v.set_content(if follow { " [follow]" } else { " [scrolled]" });
+        }
+    }
+
+    /// Freezes the content view while new lines keep being buffered.
+    fn on_toggle_pause(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let paused = match siv.user_data::<State>() {
+            Some(state) => {
+                state.paused = !state.paused;
+                state.paused
+            }
+            None => return,
+        };
+        tx.send(Event::Pause(paused)).unwrap();
+    }
+
+    fn on_toggle_matching(siv: &mut Cursive, tx: &std::sync::mpsc::Sender<Event>) {
+        let matching = match siv.user_data::<State>() {
+            Some(state) => {
+                state.matching = match state.matching {
+                    Matching::Filter => Matching::Highlight,
+                    Matching::Highlight => Matching::Filter,
+                };
+                state.matching
+            }
+            None => return,
+        };
+        if let Some(mut v) = siv.find_name::<TextView>(PROMPT_VIEW) {
+            v.set_content(matching.prompt());
+        }
+        tx.send(Event::Matching(matching)).unwrap();
+    }
+
+    fn on_submit_history(siv: &mut Cursive, _cmd: &str) {
+        if let Some(mut v) = siv.find_name::<CommandHide>(COMMAND_HIDE) {
+            v.unhide();
+        }
+        if let Some(mut v) = siv.find_name::<SelectView>(HISTORY_VIEW) {
+            if let Some(id) = v.selected_id() {
+                let item = v.get_item(id).unwrap().1.clone();
+                v.remove_item(id);
+                v.add_item_str(item.clone());
+                if let Some(mut c) = siv.find_name::<EditView>(COMMAND_VIEW) {
+                    c.set_content(item);
+                }
+                if let Some(mut h) = siv.find_name::<HistoryHide>(HISTORY_HIDE) {
+                    h.hide();
+                }
+                siv.focus_name(COMMAND_VIEW).unwrap();
+            }
+        }
+        if let Some(mut v) = siv.find_name::<ContentEnableable>(CONTENT_ENABLE) {
+            v.enable();
+        }
+    }
+
+    fn load(path: &str) -> (Vec<String>, Vec<(String, bool)>) {
+        let (mut history, mut mutes) = (Vec::new(), Vec::new());
+        if let Ok(content) = std::fs::read_to_string(path) {
+            let mut section = "";
+            for line in content.lines() {
+                match (section, line) {
+                    (_, "[History]") | (_, "[Mute]") => section = line,
+                    ("[History]", _) => history.push(line.to_owned()),
+                    ("[Mute]", _) => {
+                        let mute = match line.strip_prefix("[x] ") {
+                            Some(m) => Some((m, true)),
+                            None => line.strip_prefix("[ ] ").map(|m| (m, false)),
+                        };
+                        if let Some((m, enabled)) = mute.filter(|(m, _)| !m.is_empty()) {
+                            mutes.push((m.to_owned(), enabled));
+                        }
+                    }
+                    (_, _) => {}
+                }
+            }
+        }
+        (history, mutes)
+    }
+
+    /// Cancels a search, closes the detail pane if it's open and quits
+    /// otherwise.
+    fn on_escape(siv: &mut Cursive, path: &Option<String>) {
+        if siv
+            .user_data::<State>()
+            .is_some_and(|s| s.searching.is_some())
+        {
+            Tui::on_cancel_search(siv);
+            return;
+        }
+        let detail = siv
+            .find_name::<DetailHide>(DETAIL_HIDE)
+            .is_some_and(|v| v.is_visible());
+        match detail {
+            true => Tui::on_close_detail(siv),
+            false => Tui::quit(siv, path),
+        }
+    }
+
+    fn quit(siv: &mut Cursive, path: &Option<String>) {
+        if let Some(p) = path {
+            if let Some(v) = siv.find_name::<SelectView>(HISTORY_VIEW) {
+                if let Ok(mut f) = std::fs::File::create(p) {
+                    if f.write_fmt(format_args!("[History]\n")).is_ok() {
+                        for item in v.iter() {
+                            if f.write_fmt(format_args!("{}\n", item.0)).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    let mutes = siv
+                        .user_data::<State>()
+                        .map(|s| s.mutes.clone())
+                        .unwrap_or_default();
+                    if f.write_fmt(format_args!("[Mute]\n")).is_ok() {
+                        for (pattern, enabled) in mutes.iter() {
+                            let mark = if *enabled { "[x]" } else { "[ ]" };
+                            if f.write_fmt(format_args!("{} {}\n", mark, pattern)).is_err() {
                                 break;
                             }
                         }